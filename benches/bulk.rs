@@ -0,0 +1,26 @@
+use std::hint::black_box;
+
+use clap::Parser;
+use criterion::{Criterion, Throughput, criterion_group, criterion_main};
+use greeting::{Args, run};
+
+const NAMES: usize = 100_000;
+
+fn bulk(c: &mut Criterion) {
+    let names: Vec<String> = (0..NAMES).map(|i| format!("User{}", i)).collect();
+    let args = Args::parse_from(["greeting", "--color", "never", "--user", &names.join(",")]);
+
+    let mut group = c.benchmark_group("bulk");
+    group.throughput(Throughput::Elements(NAMES as u64));
+    group.bench_function("run", |b| {
+        let mut out = Vec::new();
+        b.iter(|| {
+            out.clear();
+            run(black_box(&args), &mut out).unwrap();
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bulk);
+criterion_main!(benches);
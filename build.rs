@@ -0,0 +1,64 @@
+use std::env;
+use std::path::Path;
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+fn main() {
+    println!(
+        "cargo:rustc-env=GIT_HASH={}",
+        git_hash().unwrap_or_else(unknown)
+    );
+    println!(
+        "cargo:rustc-env=BUILD_DATE={}",
+        build_date().unwrap_or_else(unknown)
+    );
+
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    if Path::new(".git/HEAD").exists() {
+        println!("cargo:rerun-if-changed=.git/HEAD");
+        if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]) {
+            println!("cargo:rerun-if-changed=.git/{}", branch);
+        }
+    }
+}
+
+fn unknown() -> String {
+    "unknown".to_string()
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let text = String::from_utf8(output.stdout).ok()?;
+    Some(text.trim().to_string()).filter(|text| !text.is_empty())
+}
+
+fn git_hash() -> Option<String> {
+    git(&["rev-parse", "--short=7", "HEAD"])
+}
+
+/// Today's UTC date as `YYYY-MM-DD`, honoring `SOURCE_DATE_EPOCH` for reproducible builds.
+fn build_date() -> Option<String> {
+    let secs = match env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => epoch.parse::<i64>().ok()?,
+        Err(_) => SystemTime::now().duration_since(UNIX_EPOCH).ok()?.as_secs() as i64,
+    };
+    let (year, month, day) = civil_from_days(secs.div_euclid(86_400));
+    Some(format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+/// Converts days since 1970-01-01 to a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
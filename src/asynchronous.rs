@@ -0,0 +1,36 @@
+use std::io;
+use std::path::Path;
+
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+use crate::config::{config_path, parse_config};
+use crate::{Config, ConfigError, Environment, Output, greet};
+
+/// Async version of [`greet`]; it never blocks, so it completes immediately.
+pub async fn greet_async(name: &str) -> Output {
+    greet(name)
+}
+
+/// Async version of [`load_config`](crate::load_config) using `tokio::fs`.
+pub async fn load_config_async(path: Option<&Path>) -> Result<Config, ConfigError> {
+    let Some((path, explicit)) = config_path(path, &Environment::Process) else {
+        return Ok(Config::default());
+    };
+    let contents = fs::read_to_string(&path).await;
+    parse_config(path, explicit, contents)
+}
+
+/// Async version of [`append_history`](crate::append_history) using `tokio::fs`.
+pub async fn append_history_async(path: &Path, entry: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).await?;
+    }
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    file.write_all(format!("{}\n", entry).as_bytes()).await?;
+    file.flush().await
+}
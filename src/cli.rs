@@ -0,0 +1,1007 @@
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local, Timelike, Utc};
+use clap::builder::RangedU64ValueParser;
+use clap::{CommandFactory, Parser, Subcommand};
+use clap_complete::{Generator, Shell};
+use log::{debug, info};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::Serialize;
+
+use crate::config::load_config_in;
+use crate::history::default_history_path_in;
+use crate::lang::locale_language_in;
+use crate::template::render_template_locating_name;
+use crate::xml::first_unrepresentable_char;
+use crate::{
+    CaseMode, ColorChoice, Config, ConfigError, EMOJI_PREFIX, EMOJI_SUFFIX, Envelope, Environment,
+    Format, GreetingError, Language, MessageBuilder, NormForm, Output, Phrase, TemplateContext,
+    append_history, dedupe_preserving_order, greeting_word_for_hour, highlight_name, join_names,
+    language, message_stats, normalize_name, os_name_to_string, pick_greeting, read_names_from,
+    read_names_from_file, supported_formats, supported_languages, transform_name, validate_name,
+    wrap_text,
+};
+
+/// The `--version` string, e.g. `0.1.0 (abc1234 2024-01-02)`.
+pub const VERSION: &str = concat!(
+    env!("CARGO_PKG_VERSION"),
+    " (",
+    env!("GIT_HASH"),
+    " ",
+    env!("BUILD_DATE"),
+    ")"
+);
+
+#[derive(Parser)]
+#[command(name = "greeting")]
+#[command(about = "A simple greeting application")]
+#[command(version = VERSION)]
+#[command(args_conflicts_with_subcommands = true)]
+pub struct Args {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Options for the implicit `greet` subcommand.
+    #[command(flatten)]
+    pub greet: GreetArgs,
+}
+
+/// The options for greeting, given either to `greet` or with no subcommand.
+#[derive(clap::Args)]
+pub struct GreetArgs {
+    /// Name of the user to greet (repeatable, or comma-separated) [env: GREETING_USER] [default: World]
+    #[arg(short, long, value_delimiter = ',')]
+    pub user: Vec<OsString>,
+
+    /// Read names from standard input, one per line
+    #[arg(long, conflicts_with = "user")]
+    pub stdin: bool,
+
+    /// Read names from a file, one per line; blank lines and `#` comments are skipped
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["user", "stdin"])]
+    pub file: Option<PathBuf>,
+
+    /// Prompt for names one at a time until EOF or an empty line; JSON and XML
+    /// are written as one document once the session ends
+    #[arg(short, long, conflicts_with_all = ["user", "stdin", "file"])]
+    pub interactive: bool,
+
+    /// Accept names containing control characters such as newlines; XML
+    /// output still rejects those it can't represent
+    #[arg(long)]
+    pub allow_control: bool,
+
+    /// Normalize names to a Unicode normalization form before greeting them
+    #[arg(long, value_enum, value_name = "FORM")]
+    pub normalize: Option<NormForm>,
+
+    /// Uppercase names before greeting them
+    #[arg(long, conflicts_with_all = ["lowercase", "title_case"])]
+    pub uppercase: bool,
+
+    /// Lowercase names before greeting them
+    #[arg(long, conflicts_with = "title_case")]
+    pub lowercase: bool,
+
+    /// Capitalize the first letter of each word in names, leaving the rest as-is
+    #[arg(long)]
+    pub title_case: bool,
+
+    /// Greet a name every time it is given, not just the first
+    #[arg(long)]
+    pub allow_duplicates: bool,
+
+    /// Greet all names in a single message, e.g. "Hello, Alice, Bob, and Carol!"
+    #[arg(long)]
+    pub combine: bool,
+
+    /// Emit the whole list of greetings this many times
+    #[arg(long, default_value_t = 1, value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub repeat: usize,
+
+    /// Greeting template; `{name}` is replaced with the user's name, and `{upper}`, `{lower}`,
+    /// `{len}`, `{index}` and `{total}` are also available [default: "Hello, {name}!"]
+    #[arg(long)]
+    pub template: Option<String>,
+
+    /// Read the greeting template from a file
+    #[arg(long, value_name = "PATH", conflicts_with = "template")]
+    pub template_file: Option<PathBuf>,
+
+    /// Greet with "Good morning", "Good evening", etc. based on the local time
+    #[arg(long, conflicts_with_all = ["template", "template_file", "lang"])]
+    pub time_aware: bool,
+
+    /// Decorate each greeting with a waving hand and a sparkle
+    #[arg(long)]
+    pub emoji: bool,
+
+    /// Text between the greeting word and the name; ignored with a template [default: ", "]
+    #[arg(long, value_name = "STR")]
+    pub separator: Option<String>,
+
+    /// Punctuation ending the default message; ignored with a template [default: "!"]
+    #[arg(long, value_name = "STR")]
+    pub punctuation: Option<String>,
+
+    /// Pick a random greeting word ("Hi", "Hey", "Howdy", ...) for each name
+    #[arg(long, conflicts_with_all = ["time_aware", "farewell", "template", "template_file", "lang"])]
+    pub random: bool,
+
+    /// Seed for --random, for reproducible output
+    #[arg(long, requires = "random")]
+    pub seed: Option<u64>,
+
+    /// Say goodbye instead of hello
+    #[arg(long, conflicts_with = "time_aware")]
+    pub farewell: bool,
+
+    /// Language to greet in (en, es, fr, de, ja) [default: from LC_ALL or LANG, else en]
+    #[arg(long)]
+    pub lang: Option<String>,
+
+    /// Output format [default: plain]
+    #[arg(long, value_enum)]
+    pub format: Option<Format>,
+
+    /// Indent JSON output for readability
+    #[arg(long)]
+    pub pretty: bool,
+
+    /// Emit JSON as an object mapping each name to its greeting, sorted by
+    /// name; with `--allow-duplicates`, a repeated name keeps its last greeting
+    #[arg(long, conflicts_with = "timestamp")]
+    pub json_map: bool,
+
+    /// Wrap JSON output as `{"count": N, "greetings": [...]}`
+    #[arg(long, conflicts_with = "json_map")]
+    pub envelope: bool,
+
+    /// Wrap plain-text output at this many columns
+    #[arg(long, value_name = "WIDTH", value_parser = RangedU64ValueParser::<usize>::new().range(1..))]
+    pub wrap: Option<usize>,
+
+    /// Highlight names in plain-text output
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto)]
+    pub color: ColorChoice,
+
+    /// Alias for `--format json`, kept for backward compatibility
+    #[arg(long, hide = true, conflicts_with = "format")]
+    pub json: bool,
+
+    /// Include an ISO 8601 timestamp with each greeting
+    #[arg(long)]
+    pub timestamp: bool,
+
+    /// Append each greeting to ~/.local/share/greeting/history.log
+    #[arg(long)]
+    pub log_history: bool,
+
+    /// Write greetings to this file instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    pub output: Option<PathBuf>,
+
+    /// Print the supported formats and languages as JSON and exit
+    #[arg(long, conflicts_with = "interactive")]
+    pub list_capabilities: bool,
+
+    /// Print the settings that would be used instead of greeting anyone
+    #[arg(long, conflicts_with = "interactive")]
+    pub dry_run: bool,
+
+    /// Report the characters, bytes and words of each greeting on stderr
+    #[arg(long)]
+    pub stats: bool,
+
+    /// Build and validate greetings without printing them
+    #[arg(short, long)]
+    pub quiet: bool,
+
+    /// Log diagnostics to stderr (repeat for more detail)
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Config file to read defaults from [default: ~/.config/greeting/config.toml]
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Where environment variables such as the locale are read from.
+    #[arg(skip)]
+    pub environment: Environment,
+
+    /// Whether greetings are written to a terminal, which `--color auto`
+    /// colors. Only the caller knows where its writer leads.
+    #[arg(skip)]
+    pub to_terminal: bool,
+
+    /// The instant `--timestamp` records, or the current time if unset.
+    #[arg(skip)]
+    pub now: Option<DateTime<Utc>>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Greet users; the default when no subcommand is given
+    Greet(Box<GreetArgs>),
+    /// Print a shell completion script to stdout
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+    /// Print version information
+    Version,
+}
+
+impl Args {
+    /// The greeting options, from the `greet` subcommand or the top level.
+    pub fn greet_args(&self) -> &GreetArgs {
+        match &self.command {
+            Some(Command::Greet(greet)) => greet,
+            _ => &self.greet,
+        }
+    }
+
+    fn greet_args_mut(&mut self) -> &mut GreetArgs {
+        match &mut self.command {
+            Some(Command::Greet(greet)) => greet,
+            _ => &mut self.greet,
+        }
+    }
+
+    /// Sets whether the writer passed to [`run`] is a terminal.
+    pub fn with_terminal(mut self, to_terminal: bool) -> Self {
+        self.greet_args_mut().to_terminal = to_terminal;
+        self
+    }
+
+    /// Timestamps greetings with `now` instead of the current time.
+    pub fn with_now(mut self, now: DateTime<Utc>) -> Self {
+        self.greet_args_mut().now = Some(now);
+        self
+    }
+
+    /// Reads environment variables from `environment` instead of the process.
+    pub fn with_environment(mut self, environment: Environment) -> Self {
+        self.greet_args_mut().environment = environment;
+        self
+    }
+}
+
+impl GreetArgs {
+    /// Loads the config file named by `--config`, or the default one.
+    pub fn load_config(&self) -> Result<Config, ConfigError> {
+        load_config_in(self.config.as_deref(), &self.environment)
+    }
+
+    /// The effective output format, taking the `--json` alias and `config` into account.
+    pub fn output_format(&self, config: &Config) -> Format {
+        if self.json {
+            Format::Json
+        } else if let Some(format) = self.format {
+            format
+        } else if let Some(format) = config.format {
+            debug!("using format {:?} from config file", format);
+            format
+        } else {
+            Format::default()
+        }
+    }
+
+    /// The output format to greet in, rejecting `--json-map` and `--envelope`
+    /// for other formats.
+    fn greeting_format(&self, config: &Config) -> Result<Format, GreetingError> {
+        let format = self.output_format(config);
+        let flag = if self.json_map {
+            "--json-map"
+        } else if self.envelope {
+            "--envelope"
+        } else {
+            return Ok(format);
+        };
+        if format != Format::Json {
+            return Err(GreetingError::NeedsJson { flag, format });
+        }
+        Ok(format)
+    }
+
+    fn case_mode(&self) -> CaseMode {
+        if self.uppercase {
+            CaseMode::Upper
+        } else if self.lowercase {
+            CaseMode::Lower
+        } else if self.title_case {
+            CaseMode::Title
+        } else {
+            CaseMode::None
+        }
+    }
+
+    fn use_color(&self) -> bool {
+        let no_color = self
+            .environment
+            .var_os("NO_COLOR")
+            .is_some_and(|value| !value.is_empty());
+        self.color.should_colorize(self.to_terminal, no_color)
+    }
+
+    fn phrase(&self) -> Phrase {
+        if self.farewell {
+            Phrase::Goodbye
+        } else {
+            Phrase::Hello
+        }
+    }
+
+    /// The `--lang` code, falling back to the locale for plain greetings.
+    ///
+    /// `--time-aware` and `--random` only have English words, so they ignore the locale.
+    fn lang_code(&self) -> Cow<'_, str> {
+        if let Some(lang) = &self.lang {
+            return Cow::Borrowed(lang);
+        }
+        if self.time_aware || self.random {
+            return Cow::Borrowed("en");
+        }
+        match locale_language_in(&self.environment) {
+            Some(lang) => {
+                debug!("using language {} from the locale", lang);
+                Cow::Owned(lang)
+            }
+            None => Cow::Borrowed("en"),
+        }
+    }
+
+    fn language(&self) -> Result<&'static Language, GreetingError> {
+        let code = self.lang_code();
+        language(&code).ok_or_else(|| GreetingError::UnsupportedLanguage(code.into_owned()))
+    }
+
+    fn message(&self) -> Result<Message<'_>, GreetingError> {
+        if let Some(path) = &self.template_file {
+            let template = read_template_file(path)?;
+            return Ok(Message::Template(
+                self.decorate_template(Cow::Owned(template)),
+            ));
+        }
+        match &self.template {
+            Some(template) => Ok(Message::Template(
+                self.decorate_template(Cow::Borrowed(template)),
+            )),
+            None if self.random => {
+                let rng = Box::new(match self.seed {
+                    Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+                    None => rand::make_rng(),
+                });
+                Ok(Message::Random {
+                    builder: self.message_builder()?,
+                    rng,
+                })
+            }
+            None => self.message_builder().map(Message::Builder),
+        }
+    }
+
+    /// Adds `--emoji` decoration to `template`; the emoji contain no braces, so
+    /// they never form placeholders.
+    fn decorate_template<'a>(&self, template: Cow<'a, str>) -> Cow<'a, str> {
+        if self.emoji {
+            Cow::Owned(format!("{}{}{}", EMOJI_PREFIX, template, EMOJI_SUFFIX))
+        } else {
+            template
+        }
+    }
+
+    /// The builder for the default message, used when no `--template` is given.
+    fn message_builder(&self) -> Result<MessageBuilder<'_>, GreetingError> {
+        let language = self.language()?;
+        let word = if self.time_aware {
+            greeting_word_for_hour(Local::now().hour())
+        } else {
+            language.word(self.phrase())
+        };
+        Ok(MessageBuilder {
+            word,
+            separator: self.separator.as_deref().unwrap_or(language.separator),
+            punctuation: self.punctuation.as_deref().unwrap_or(language.punctuation),
+            emoji: self.emoji,
+        })
+    }
+
+    /// Merges flags, the environment and `config` into the settings used to greet `names`.
+    pub fn resolve(
+        &self,
+        config: &Config,
+        names: &[String],
+    ) -> Result<ResolvedConfig, GreetingError> {
+        let names = self.prepare_names(names)?;
+        let mut resolved = ResolvedConfig {
+            names,
+            repeat: self.repeat,
+            format: self.output_format(config),
+            template: None,
+            language: None,
+            word: None,
+            separator: None,
+            punctuation: None,
+            emoji: self.emoji,
+            timestamp: self.timestamp,
+        };
+        let builder = match self.message()? {
+            Message::Template(template) => {
+                resolved.template = Some(template.into_owned());
+                return Ok(resolved);
+            }
+            Message::Builder(builder) => {
+                resolved.word = Some(builder.word.to_string());
+                builder
+            }
+            Message::Random { builder, .. } => builder,
+        };
+        resolved.language = Some(self.language()?.code.to_string());
+        resolved.separator = Some(builder.separator.to_string());
+        resolved.punctuation = Some(builder.punctuation.to_string());
+        Ok(resolved)
+    }
+
+    /// The names to greet, from stdin, a file, flags, the environment or `config`.
+    fn names(&self, config: &Config) -> Result<Vec<String>, GreetingError> {
+        let names = if self.stdin {
+            let names = read_names_from(io::stdin().lock()).map_err(|err| {
+                io::Error::new(
+                    err.kind(),
+                    format!("failed to read names from stdin: {}", err),
+                )
+            })?;
+            info!("read {} names from stdin", names.len());
+            names
+        } else if let Some(path) = &self.file {
+            let names = read_names_from_file(path)?;
+            info!("read {} names from {}", names.len(), path.display());
+            names
+        } else if !self.user.is_empty() {
+            self.user
+                .iter()
+                .map(|user| {
+                    let (name, lossy) = os_name_to_string(user);
+                    if lossy {
+                        eprintln!(
+                            "warning: name {:?} is not valid UTF-8, using {:?}",
+                            user, name
+                        );
+                    }
+                    name
+                })
+                .collect()
+        } else if let Some(user) = self.environment.var("GREETING_USER") {
+            info!("using name from GREETING_USER");
+            vec![user]
+        } else if let Some(user) = &config.user {
+            info!("using name from config file");
+            vec![user.clone()]
+        } else {
+            info!("no name given, using the default");
+            vec!["World".to_string()]
+        };
+        Ok(names)
+    }
+
+    /// Applies name transformations to `names` and validates the results,
+    /// joining them into one name with `--combine`.
+    fn prepare_names(&self, names: &[String]) -> Result<Vec<String>, GreetingError> {
+        let case = self.case_mode();
+        let names: Vec<String> = names
+            .iter()
+            .map(|name| match self.normalize {
+                Some(form) => transform_name(&normalize_name(name, form), case),
+                None => transform_name(name, case),
+            })
+            .collect();
+        let names = if self.allow_duplicates {
+            names
+        } else {
+            dedupe_preserving_order(names)
+        };
+        if !self.allow_control {
+            for name in &names {
+                validate_name(name)?;
+            }
+        }
+        if self.combine && !names.is_empty() {
+            return Ok(vec![join_names(&names)]);
+        }
+        Ok(names)
+    }
+}
+
+/// The settings a run would greet with, as printed by `--dry-run`.
+///
+/// `template` and the default-message fields are mutually exclusive; `word`
+/// is also unset with `--random`, which picks a word per greeting.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ResolvedConfig {
+    pub names: Vec<String>,
+    pub repeat: usize,
+    pub format: Format,
+    pub template: Option<String>,
+    pub language: Option<String>,
+    pub word: Option<String>,
+    pub separator: Option<String>,
+    pub punctuation: Option<String>,
+    pub emoji: bool,
+    pub timestamp: bool,
+}
+
+impl ResolvedConfig {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serializing settings cannot fail")
+    }
+
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializing settings cannot fail")
+    }
+
+    /// One `key: value` line per setting; strings are quoted so that
+    /// whitespace and empty values stay visible.
+    pub fn to_plain_text(&self) -> String {
+        let quoted = |value: &Option<String>| match value {
+            Some(value) => format!("{:?}", value),
+            None => "none".to_string(),
+        };
+        let names: Vec<String> = self
+            .names
+            .iter()
+            .map(|name| format!("{:?}", name))
+            .collect();
+        [
+            format!("names: {}", names.join(", ")),
+            format!("repeat: {}", self.repeat),
+            format!("format: {}", self.format.name()),
+            format!("template: {}", quoted(&self.template)),
+            format!("language: {}", self.language.as_deref().unwrap_or("none")),
+            format!("word: {}", quoted(&self.word)),
+            format!("separator: {}", quoted(&self.separator)),
+            format!("punctuation: {}", quoted(&self.punctuation)),
+            format!("emoji: {}", self.emoji),
+            format!("timestamp: {}", self.timestamp),
+        ]
+        .join("\n")
+    }
+}
+
+/// What this build supports, as printed by `--list-capabilities`.
+#[derive(Serialize)]
+struct Capabilities {
+    formats: Vec<&'static str>,
+    languages: Vec<&'static str>,
+}
+
+/// Reads a template file verbatim, except for a single trailing line ending
+/// since each greeting is already written on its own line.
+fn read_template_file(path: &Path) -> io::Result<String> {
+    let mut template = fs::read_to_string(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            format!("failed to read template file {}: {}", path.display(), err),
+        )
+    })?;
+    if template.ends_with('\n') {
+        template.pop();
+        if template.ends_with('\r') {
+            template.pop();
+        }
+    }
+    Ok(template)
+}
+
+/// How each name is turned into a message.
+enum Message<'a> {
+    Template(Cow<'a, str>),
+    Builder(MessageBuilder<'a>),
+    /// Like `Builder`, but with a freshly picked word for every name.
+    Random {
+        builder: MessageBuilder<'a>,
+        rng: Box<ChaCha8Rng>,
+    },
+}
+
+impl Message<'_> {
+    /// Renders the greeting for `context`, with the byte offset of the name
+    /// in its message if it appears there verbatim.
+    fn render(
+        &mut self,
+        context: &TemplateContext,
+    ) -> Result<(Output, Option<usize>), GreetingError> {
+        let builder = match self {
+            Message::Template(template) => {
+                let (message, name_start) = render_template_locating_name(template, context)?;
+                return Ok((Output::new(message), name_start));
+            }
+            Message::Builder(builder) => *builder,
+            Message::Random { builder, rng } => MessageBuilder {
+                word: pick_greeting(rng),
+                ..*builder
+            },
+        };
+        Ok((builder.build(context.name), Some(builder.name_offset())))
+    }
+}
+
+/// Greets every requested user and writes the result to `out`.
+pub fn run(args: &Args, out: &mut dyn Write) -> Result<(), GreetingError> {
+    match args.command {
+        Some(Command::Completions { shell }) => {
+            let mut cmd = Args::command();
+            cmd.set_bin_name("greeting");
+            cmd.build();
+            return Ok(shell.try_generate(&cmd, out)?);
+        }
+        Some(Command::Version) => {
+            write!(out, "{}", Args::command().render_version())?;
+            return Ok(());
+        }
+        Some(Command::Greet(_)) | None => {}
+    }
+
+    let args = args.greet_args();
+    if args.list_capabilities {
+        let capabilities = Capabilities {
+            formats: supported_formats(),
+            languages: supported_languages(),
+        };
+        return Ok(write_json(out, &capabilities, args.pretty)?);
+    }
+    let config = args.load_config()?;
+    let names = args.names(&config)?;
+    if args.dry_run {
+        let resolved = args.resolve(&config, &names)?;
+        let text = match resolved.format {
+            Format::Json if args.pretty => resolved.to_json_pretty(),
+            Format::Json => resolved.to_json(),
+            _ => resolved.to_plain_text(),
+        };
+        writeln!(out, "{}", text)?;
+        return Ok(());
+    }
+    let names = args.prepare_names(&names)?;
+    let format = args.greeting_format(&config)?;
+    let mut message = args.message()?;
+    greet_names(args, &mut message, &names, format, true, out)
+}
+
+/// Prompts on stderr for names read from `input` and writes a greeting for
+/// each to `out`, stopping at EOF or an empty line.
+///
+/// Each name is validated as it arrives and is greeted only once per session
+/// unless `--allow-duplicates` is given. Greetings are written as each name
+/// arrives, with a CSV header only before the first; JSON and XML are single
+/// documents, so their greetings are written together when the session ends.
+pub fn run_interactive<R: BufRead, W: Write>(
+    args: &Args,
+    input: R,
+    mut out: W,
+) -> Result<(), GreetingError> {
+    let args = args.greet_args();
+    let config = args.load_config()?;
+    let format = args.greeting_format(&config)?;
+    let mut message = args.message()?;
+    let mut seen = HashSet::new();
+    let mut pending = matches!(format, Format::Json | Format::Xml).then(Pending::default);
+    let mut header = true;
+    let mut lines = input.lines();
+    loop {
+        eprint!("name> ");
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        let name = line.trim_end();
+        if name.is_empty() {
+            break;
+        }
+        let mut names = args.prepare_names(&[name.to_string()])?;
+        if !args.allow_duplicates {
+            names.retain(|name| seen.insert(name.clone()));
+        }
+        if names.is_empty() {
+            continue;
+        }
+        match &mut pending {
+            Some(pending) => {
+                let total = greeting_count(args, &names)?;
+                let users = || names.iter().cycle().take(total);
+                let (outputs, name_starts) =
+                    render_greetings(args, format, &mut message, users(), total)?;
+                pending.users.extend(users().cloned());
+                pending.outputs.extend(outputs);
+                pending.name_starts.extend(name_starts);
+            }
+            None => {
+                greet_names(args, &mut message, &names, format, header, &mut out)?;
+                header = false;
+                out.flush()?;
+            }
+        }
+    }
+    if let Some(pending) = pending
+        && !pending.outputs.is_empty()
+        && !args.quiet
+    {
+        let users = pending.users.iter();
+        write_greetings(
+            args,
+            format,
+            users,
+            &pending.outputs,
+            &pending.name_starts,
+            true,
+            &mut out,
+        )?;
+        out.flush()?;
+        report_stats(args, &pending.outputs);
+    }
+    Ok(())
+}
+
+/// Greetings an interactive session keeps to write as one document.
+#[derive(Default)]
+struct Pending {
+    users: Vec<String>,
+    outputs: Vec<Output>,
+    name_starts: Vec<Option<usize>>,
+}
+
+/// The number of greetings `names` make with `--repeat`.
+fn greeting_count(args: &GreetArgs, names: &[String]) -> Result<usize, GreetingError> {
+    names
+        .len()
+        .checked_mul(args.repeat)
+        .ok_or(GreetingError::TooManyGreetings {
+            names: names.len(),
+            repeat: args.repeat,
+        })
+}
+
+/// Greets `names`, already prepared, and writes them to `out` in `format`,
+/// starting CSV output with a header row if `header` is set.
+fn greet_names(
+    args: &GreetArgs,
+    message: &mut Message,
+    names: &[String],
+    format: Format,
+    header: bool,
+    out: &mut dyn Write,
+) -> Result<(), GreetingError> {
+    let total = greeting_count(args, names)?;
+    let users = || names.iter().cycle().take(total);
+    // The common bulk case needs no per-greeting `Output`, so skip building them.
+    if let Message::Builder(builder) = message
+        && format == Format::Plain
+        && !args.timestamp
+        && !args.log_history
+        && args.wrap.is_none()
+        && !args.use_color()
+        && !args.stats
+    {
+        info!("produced {} greetings", total);
+        if args.quiet {
+            return Ok(());
+        }
+        return Ok(write_plain_bulk(builder, users(), out)?);
+    }
+
+    let (outputs, name_starts) = render_greetings(args, format, message, users(), total)?;
+    if args.quiet {
+        return Ok(());
+    }
+    write_greetings(args, format, users(), &outputs, &name_starts, header, out)?;
+    report_stats(args, &outputs);
+    Ok(())
+}
+
+/// Renders the greeting for each of `users`, with the byte offset of the
+/// name in each message, and logs them to the history if asked.
+///
+/// Fails if `format` can't represent a greeting, before any is written.
+fn render_greetings<'a>(
+    args: &GreetArgs,
+    format: Format,
+    message: &mut Message,
+    users: impl Iterator<Item = &'a String>,
+    total: usize,
+) -> Result<(Vec<Output>, Vec<Option<usize>>), GreetingError> {
+    let rendered: Result<Vec<_>, GreetingError> = users
+        .enumerate()
+        .map(|(i, user)| {
+            message.render(&TemplateContext {
+                name: user,
+                index: i + 1,
+                total,
+            })
+        })
+        .collect();
+    let (mut outputs, name_starts): (Vec<Output>, Vec<Option<usize>>) =
+        rendered?.into_iter().unzip();
+    if format == Format::Xml
+        && let Some(character) = outputs
+            .iter()
+            .find_map(|output| first_unrepresentable_char(output.message()))
+    {
+        return Err(GreetingError::Unrepresentable { format, character });
+    }
+    if args.timestamp {
+        let now = args.now.unwrap_or_else(Utc::now);
+        outputs = outputs
+            .into_iter()
+            .map(|output| output.with_timestamp(now))
+            .collect();
+    }
+
+    info!("produced {} greetings", outputs.len());
+    if args.log_history {
+        log_history(&outputs, &args.environment);
+    }
+    Ok((outputs, name_starts))
+}
+
+/// Writes `outputs`, greeting `users` in turn, to `out` in `format`.
+fn write_greetings<'a>(
+    args: &GreetArgs,
+    format: Format,
+    users: impl Iterator<Item = &'a String>,
+    outputs: &[Output],
+    name_starts: &[Option<usize>],
+    header: bool,
+    out: &mut dyn Write,
+) -> Result<(), GreetingError> {
+    let mut out = BufWriter::new(out);
+    match format {
+        Format::Plain => {
+            let color = args.use_color();
+            for ((user, output), name_start) in users.zip(outputs).zip(name_starts) {
+                let mut text = output.to_plain_text();
+                let mut name_start = *name_start;
+                if let Some(width) = args.wrap {
+                    let wrapped = wrap_text(&text, width);
+                    name_start = name_start.map(|start| wrapped_offset(&text, &wrapped, start));
+                    text = wrapped;
+                }
+                if color && let Some(start) = name_start {
+                    text = highlight_name(&text, user, start);
+                }
+                writeln!(out, "{}", text)?;
+            }
+        }
+        Format::Json if args.json_map => {
+            let map: BTreeMap<&str, &str> = users
+                .zip(outputs)
+                .map(|(user, output)| (user.as_str(), output.message()))
+                .collect();
+            write_json(&mut out, &map, args.pretty)?;
+        }
+        Format::Json if args.envelope => {
+            write_json(&mut out, &Envelope::new(outputs), args.pretty)?
+        }
+        Format::Json => write_json(&mut out, &outputs, args.pretty)?,
+        Format::Ndjson => {
+            for output in outputs {
+                writeln!(out, "{}", output.to_json())?;
+                out.flush()?;
+            }
+        }
+        Format::Yaml => {
+            for output in outputs {
+                writeln!(out, "- {}", output.to_yaml().replace('\n', "\n  "))?;
+            }
+        }
+        Format::Toml => {
+            let tables: Vec<String> = outputs
+                .iter()
+                .map(|output| format!("[[greetings]]\n{}", output.to_toml()))
+                .collect();
+            writeln!(out, "{}", tables.join("\n\n"))?;
+        }
+        Format::Xml => match outputs {
+            [output] => writeln!(out, "{}", output.to_xml())?,
+            outputs => {
+                writeln!(out, "<greetings>")?;
+                for output in outputs {
+                    writeln!(out, "  {}", output.to_xml())?;
+                }
+                writeln!(out, "</greetings>")?;
+            }
+        },
+        Format::Csv => {
+            if header && args.timestamp {
+                writeln!(out, "message,timestamp")?;
+            } else if header {
+                writeln!(out, "message")?;
+            }
+            for output in outputs {
+                writeln!(out, "{}", output.to_csv_record())?;
+            }
+        }
+    }
+    Ok(out.flush()?)
+}
+
+/// Reports the characters, bytes and words of each of `outputs` on stderr if asked.
+fn report_stats(args: &GreetArgs, outputs: &[Output]) {
+    if !args.stats {
+        return;
+    }
+    for output in outputs {
+        let stats = message_stats(output.message());
+        eprintln!(
+            "{:?}: {} chars, {} bytes, {} words",
+            output.message(),
+            stats.chars,
+            stats.bytes,
+            stats.words
+        );
+    }
+}
+
+/// Maps byte `start` of `text` to the same position in `wrapped`, which
+/// differs from `text` only in whitespace.
+fn wrapped_offset(text: &str, wrapped: &str, start: usize) -> usize {
+    let skip = text[..start].chars().filter(|c| !c.is_whitespace()).count();
+    wrapped
+        .char_indices()
+        .filter(|(_, c)| !c.is_whitespace())
+        .nth(skip)
+        .map_or(wrapped.len(), |(i, _)| i)
+}
+
+/// Writes plain greetings for `users` through one buffered writer, reusing a
+/// single line buffer rather than allocating a message per name.
+fn write_plain_bulk<'a>(
+    builder: &MessageBuilder,
+    users: impl Iterator<Item = &'a String>,
+    out: &mut dyn Write,
+) -> io::Result<()> {
+    let mut out = BufWriter::new(out);
+    let mut line = String::new();
+    for user in users {
+        line.clear();
+        builder.build_into(&mut line, user);
+        line.push('\n');
+        out.write_all(line.as_bytes())?;
+    }
+    out.flush()
+}
+
+/// Writes `value` as a single line of JSON, or indented when `pretty`.
+fn write_json(out: &mut dyn Write, value: &impl Serialize, pretty: bool) -> io::Result<()> {
+    if pretty {
+        serde_json::to_writer_pretty(&mut *out, value)?;
+    } else {
+        serde_json::to_writer(&mut *out, value)?;
+    }
+    writeln!(out)
+}
+
+/// Records `outputs` in the history file, warning rather than failing on errors.
+fn log_history(outputs: &[Output], env: &Environment) {
+    let Some(path) = default_history_path_in(env) else {
+        eprintln!("warning: no home directory, not writing greeting history");
+        return;
+    };
+    let now = Utc::now().format("%Y-%m-%dT%H:%M:%SZ");
+    for output in outputs {
+        let entry = format!("{}\t{}", now, output.message());
+        if let Err(err) = append_history(&path, &entry) {
+            eprintln!(
+                "warning: failed to write greeting history to {}: {}",
+                path.display(),
+                err
+            );
+            return;
+        }
+    }
+}
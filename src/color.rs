@@ -0,0 +1,49 @@
+use clap::ValueEnum;
+
+const HIGHLIGHT: &str = "\x1b[1;36m";
+const RESET: &str = "\x1b[0m";
+
+/// When to highlight names in plain-text output.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum)]
+pub enum ColorChoice {
+    /// Color only when writing to a terminal and `NO_COLOR` is unset
+    #[default]
+    Auto,
+    /// Always color, even when output is piped
+    Always,
+    /// Never color
+    Never,
+}
+
+impl ColorChoice {
+    /// Decides whether to color output.
+    ///
+    /// `NO_COLOR` only affects `auto`; an explicit `--color always` still wins.
+    pub fn should_colorize(self, is_terminal: bool, no_color: bool) -> bool {
+        match self {
+            ColorChoice::Auto => is_terminal && !no_color,
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+        }
+    }
+}
+
+/// Wraps `name` in ANSI highlight codes where it starts at byte `start` of
+/// `message`, so that the same text elsewhere, such as inside the greeting
+/// word, is left alone. `message` is returned unchanged if `name` isn't there.
+pub fn highlight_name(message: &str, name: &str, start: usize) -> String {
+    match message.get(start..) {
+        Some(rest) if !name.is_empty() && rest.starts_with(name) => {
+            let end = start + name.len();
+            format!(
+                "{}{}{}{}{}",
+                &message[..start],
+                HIGHLIGHT,
+                name,
+                RESET,
+                &message[end..]
+            )
+        }
+        _ => message.to_string(),
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use log::{debug, info};
+use serde::Deserialize;
+
+use crate::{Environment, Format};
+
+/// Defaults read from a TOML config file.
+///
+/// Settings are resolved with the precedence: CLI flag > environment variable >
+/// config file > built-in default.
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub user: Option<String>,
+    pub format: Option<Format>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Read {
+        path: PathBuf,
+        source: io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read { path, source } => {
+                write!(
+                    f,
+                    "failed to read config file {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            ConfigError::Parse { path, source } => {
+                write!(f, "invalid config file {}: {}", path.display(), source)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfigError::Read { source, .. } => source.source(),
+            ConfigError::Parse { source, .. } => source.source(),
+        }
+    }
+}
+
+/// The default config location, `$XDG_CONFIG_HOME/greeting/config.toml` or
+/// `~/.config/greeting/config.toml`.
+pub fn default_config_path() -> Option<PathBuf> {
+    default_config_path_in(&Environment::Process)
+}
+
+fn default_config_path_in(env: &Environment) -> Option<PathBuf> {
+    let config_home = env
+        .var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env.var_os("HOME")
+                .map(|home| Path::new(&home).join(".config"))
+        })?;
+    Some(config_home.join("greeting").join("config.toml"))
+}
+
+/// Loads the config file at `path`, or at [`default_config_path`] when `None`.
+///
+/// A missing default file yields an empty config; a missing explicit file is an error.
+pub fn load_config(path: Option<&Path>) -> Result<Config, ConfigError> {
+    load_config_in(path, &Environment::Process)
+}
+
+/// Like [`load_config`], finding the default file through `env`.
+pub(crate) fn load_config_in(
+    path: Option<&Path>,
+    env: &Environment,
+) -> Result<Config, ConfigError> {
+    let Some((path, explicit)) = config_path(path, env) else {
+        return Ok(Config::default());
+    };
+    let contents = fs::read_to_string(&path);
+    parse_config(path, explicit, contents)
+}
+
+/// The config file to read and whether it was requested explicitly.
+pub(crate) fn config_path(path: Option<&Path>, env: &Environment) -> Option<(PathBuf, bool)> {
+    match path {
+        Some(path) => Some((path.to_path_buf(), true)),
+        None => {
+            let path = default_config_path_in(env);
+            if path.is_none() {
+                debug!("no home directory, skipping config file");
+            }
+            path.map(|path| (path, false))
+        }
+    }
+}
+
+/// Parses the result of reading the config file at `path`.
+pub(crate) fn parse_config(
+    path: PathBuf,
+    explicit: bool,
+    contents: io::Result<String>,
+) -> Result<Config, ConfigError> {
+    let contents = match contents {
+        Ok(contents) => contents,
+        Err(err) if !explicit && err.kind() == io::ErrorKind::NotFound => {
+            debug!("no config file at {}", path.display());
+            return Ok(Config::default());
+        }
+        Err(source) => return Err(ConfigError::Read { path, source }),
+    };
+    let config = toml::from_str(&contents).map_err(|source| ConfigError::Parse {
+        path: path.clone(),
+        source,
+    })?;
+    info!("loaded config file {}", path.display());
+    debug!("config: {:?}", config);
+    Ok(config)
+}
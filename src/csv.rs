@@ -0,0 +1,8 @@
+/// Quotes `field` per RFC 4180 when it contains a comma, quote or line break.
+pub(crate) fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
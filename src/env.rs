@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+use std::env;
+use std::ffi::OsString;
+
+/// Where settings such as the locale are read from.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum Environment {
+    /// The variables of the running process.
+    #[default]
+    Process,
+    /// Only the given variables, so that runs are reproducible.
+    Fixed(HashMap<String, OsString>),
+}
+
+impl Environment {
+    /// An environment with no variables set.
+    pub fn empty() -> Self {
+        Environment::Fixed(HashMap::new())
+    }
+
+    /// Sets `key` to `value`, turning a process environment into a fixed one.
+    pub fn with(self, key: &str, value: impl Into<OsString>) -> Self {
+        let mut vars = match self {
+            Environment::Process => env::vars_os()
+                .filter_map(|(key, value)| Some((key.into_string().ok()?, value)))
+                .collect(),
+            Environment::Fixed(vars) => vars,
+        };
+        vars.insert(key.to_string(), value.into());
+        Environment::Fixed(vars)
+    }
+
+    /// The value of `key`, if it is set.
+    pub fn var_os(&self, key: &str) -> Option<OsString> {
+        match self {
+            Environment::Process => env::var_os(key),
+            Environment::Fixed(vars) => vars.get(key).cloned(),
+        }
+    }
+
+    /// The value of `key`, if it is set to non-empty UTF-8.
+    pub fn var(&self, key: &str) -> Option<String> {
+        self.var_os(key)
+            .and_then(|value| value.into_string().ok())
+            .filter(|value| !value.is_empty())
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+use std::io;
+
+use crate::{ConfigError, Format, TemplateError, ValidationError, supported_languages};
+
+/// Any error produced while greeting.
+#[derive(Debug)]
+pub enum GreetingError {
+    InvalidName(ValidationError),
+    BadTemplate(TemplateError),
+    UnsupportedLanguage(String),
+    /// `--repeat` would produce more greetings than can be counted.
+    TooManyGreetings {
+        names: usize,
+        repeat: usize,
+    },
+    /// A JSON-only flag such as `--json-map` was given for output in another format.
+    NeedsJson {
+        flag: &'static str,
+        format: Format,
+    },
+    /// A greeting contains a character that `format` can't represent.
+    Unrepresentable {
+        format: Format,
+        character: char,
+    },
+    Io(io::Error),
+    Config(ConfigError),
+}
+
+impl fmt::Display for GreetingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GreetingError::InvalidName(err) => err.fmt(f),
+            GreetingError::BadTemplate(err) => err.fmt(f),
+            GreetingError::UnsupportedLanguage(code) => write!(
+                f,
+                "unsupported language `{}` (supported: {})",
+                code,
+                supported_languages().join(", ")
+            ),
+            GreetingError::TooManyGreetings { names, repeat } => write!(
+                f,
+                "too many greetings: {} names repeated {} times",
+                names, repeat
+            ),
+            GreetingError::NeedsJson { flag, format } => {
+                write!(f, "{} needs JSON output, not {}", flag, format.name())
+            }
+            GreetingError::Unrepresentable { format, character } => write!(
+                f,
+                "{} output can't represent U+{:04X}",
+                format.name(),
+                *character as u32
+            ),
+            GreetingError::Io(err) => err.fmt(f),
+            GreetingError::Config(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for GreetingError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GreetingError::InvalidName(err) => err.source(),
+            GreetingError::BadTemplate(err) => err.source(),
+            GreetingError::UnsupportedLanguage(_) => None,
+            GreetingError::TooManyGreetings { .. } => None,
+            GreetingError::NeedsJson { .. } => None,
+            GreetingError::Unrepresentable { .. } => None,
+            GreetingError::Io(err) => err.source(),
+            GreetingError::Config(err) => err.source(),
+        }
+    }
+}
+
+impl From<ValidationError> for GreetingError {
+    fn from(err: ValidationError) -> Self {
+        GreetingError::InvalidName(err)
+    }
+}
+
+impl From<TemplateError> for GreetingError {
+    fn from(err: TemplateError) -> Self {
+        GreetingError::BadTemplate(err)
+    }
+}
+
+impl From<io::Error> for GreetingError {
+    fn from(err: io::Error) -> Self {
+        GreetingError::Io(err)
+    }
+}
+
+impl From<ConfigError> for GreetingError {
+    fn from(err: ConfigError) -> Self {
+        GreetingError::Config(err)
+    }
+}
@@ -0,0 +1,34 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+use crate::Environment;
+
+/// The default history location, `$XDG_DATA_HOME/greeting/history.log` or
+/// `~/.local/share/greeting/history.log`.
+pub fn default_history_path() -> Option<PathBuf> {
+    default_history_path_in(&Environment::Process)
+}
+
+/// Like [`default_history_path`], reading the variables from `env`.
+pub(crate) fn default_history_path_in(env: &Environment) -> Option<PathBuf> {
+    let data_home = env
+        .var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            env.var_os("HOME")
+                .map(|home| Path::new(&home).join(".local/share"))
+        })?;
+    Some(data_home.join("greeting").join("history.log"))
+}
+
+/// Appends `entry` as a line to the file at `path`, creating it and any
+/// missing parent directories.
+pub fn append_history(path: &Path, entry: &str) -> io::Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", entry)
+}
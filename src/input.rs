@@ -0,0 +1,50 @@
+use std::ffi::OsStr;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+
+use crate::GreetingError;
+
+/// Reads one name per line from `reader`.
+///
+/// Trailing whitespace is trimmed and empty lines are skipped.
+pub fn read_names_from<R: BufRead>(reader: R) -> io::Result<Vec<String>> {
+    let mut names = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let name = line.trim_end();
+        if !name.is_empty() {
+            names.push(name.to_string());
+        }
+    }
+    Ok(names)
+}
+
+/// Reads one name per line from the file at `path`.
+///
+/// Lines are parsed as by [`read_names_from`], additionally trimming leading
+/// whitespace and skipping lines that start with `#`.
+pub fn read_names_from_file(path: &Path) -> Result<Vec<String>, GreetingError> {
+    let with_path = |err: io::Error| {
+        io::Error::new(
+            err.kind(),
+            format!("failed to read names from {}: {}", path.display(), err),
+        )
+    };
+    let file = File::open(path).map_err(with_path)?;
+    let names = read_names_from(BufReader::new(file)).map_err(with_path)?;
+    Ok(names
+        .into_iter()
+        .map(|name| name.trim_start().to_string())
+        .filter(|name| !name.starts_with('#'))
+        .collect())
+}
+
+/// Converts a command-line name to a `String`, replacing invalid UTF-8 with
+/// U+FFFD. The flag is true if any replacement was made.
+pub fn os_name_to_string(os: &OsStr) -> (String, bool) {
+    match os.to_str() {
+        Some(name) => (name.to_string(), false),
+        None => (os.to_string_lossy().into_owned(), true),
+    }
+}
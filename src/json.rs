@@ -0,0 +1,41 @@
+/// Escapes `s` for use inside a JSON string literal.
+///
+/// Characters outside the BMP are emitted as-is, which is valid JSON since the
+/// output is UTF-8; only quotes, backslashes and control characters need escaping.
+pub fn escape_json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            '\u{08}' => escaped.push_str("\\b"),
+            '\u{0c}' => escaped.push_str("\\f"),
+            c if (c as u32) < 0x20 => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Escapes `s` for use inside a YAML double-quoted scalar or a TOML basic string.
+///
+/// Both accept JSON's escape sequences, but unlike JSON they reject a raw DEL,
+/// C1 control characters and the noncharacters U+FFFE and U+FFFF, so those are
+/// escaped as well.
+pub(crate) fn escape_quoted_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in escape_json_string(s).chars() {
+        match c {
+            '\u{7f}'..='\u{9f}' | '\u{fffe}' | '\u{ffff}' => {
+                escaped.push_str(&format!("\\u{:04x}", c as u32));
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,128 @@
+use std::sync::LazyLock;
+
+use crate::{Environment, Phrase};
+
+/// How a language phrases its default message: `{word}{separator}{name}{punctuation}`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Language {
+    pub code: &'static str,
+    pub hello: &'static str,
+    pub goodbye: &'static str,
+    pub separator: &'static str,
+    pub punctuation: &'static str,
+}
+
+impl Language {
+    pub fn word(&self, phrase: Phrase) -> &'static str {
+        match phrase {
+            Phrase::Hello => self.hello,
+            Phrase::Goodbye => self.goodbye,
+        }
+    }
+
+    /// The default message for `phrase` as a template with a `{name}` placeholder.
+    pub fn template(&self, phrase: Phrase) -> String {
+        format!(
+            "{}{}{{name}}{}",
+            self.word(phrase),
+            self.separator,
+            self.punctuation
+        )
+    }
+}
+
+/// Supported languages, in the order they are listed to users.
+const LANGUAGES: &[Language] = &[
+    Language {
+        code: "en",
+        hello: "Hello",
+        goodbye: "Goodbye",
+        separator: ", ",
+        punctuation: "!",
+    },
+    Language {
+        code: "es",
+        hello: "¡Hola",
+        goodbye: "¡Adiós",
+        separator: ", ",
+        punctuation: "!",
+    },
+    Language {
+        code: "fr",
+        hello: "Bonjour",
+        goodbye: "Au revoir",
+        separator: ", ",
+        punctuation: " !",
+    },
+    Language {
+        code: "de",
+        hello: "Hallo",
+        goodbye: "Auf Wiedersehen",
+        separator: ", ",
+        punctuation: "!",
+    },
+    Language {
+        code: "ja",
+        hello: "こんにちは",
+        goodbye: "さようなら",
+        separator: "、",
+        punctuation: "さん！",
+    },
+];
+
+/// Looks up the language `code`, e.g. `"fr"`.
+pub fn language(code: &str) -> Option<&'static Language> {
+    LANGUAGES.iter().find(|language| language.code == code)
+}
+
+/// The hello template of each language, in table order.
+static GREETING_TEMPLATES: LazyLock<Vec<String>> = LazyLock::new(|| {
+    LANGUAGES
+        .iter()
+        .map(|language| language.template(Phrase::Hello))
+        .collect()
+});
+
+/// Looks up the greeting template for the language `code`, e.g. `"fr"`.
+pub fn greeting_template_for_lang(code: &str) -> Option<&'static str> {
+    let index = LANGUAGES
+        .iter()
+        .position(|language| language.code == code)?;
+    Some(&GREETING_TEMPLATES[index])
+}
+
+/// Looks up the template for `phrase` in the language `code`.
+pub fn template_for_lang(code: &str, phrase: Phrase) -> Option<String> {
+    language(code).map(|language| language.template(phrase))
+}
+
+/// The codes of every language with a greeting table, in table order.
+pub fn supported_languages() -> Vec<&'static str> {
+    LANGUAGES.iter().map(|language| language.code).collect()
+}
+
+/// Extracts a supported language code from a locale such as `fr_FR.UTF-8`.
+///
+/// Returns `None` for `C`, `POSIX` and languages without a greeting table.
+pub fn parse_locale_language(locale: &str) -> Option<String> {
+    let code = locale
+        .split(['_', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    language(&code).map(|language| language.code.to_string())
+}
+
+/// Detects the greeting language from `LC_ALL`, then `LANG`.
+///
+/// The first non-empty variable decides, so an unsupported `LC_ALL` is not
+/// overridden by `LANG`.
+pub fn detect_locale_language() -> Option<String> {
+    locale_language_in(&Environment::Process)
+}
+
+/// Like [`detect_locale_language`], reading the variables from `env`.
+pub(crate) fn locale_language_in(env: &Environment) -> Option<String> {
+    let locale = ["LC_ALL", "LANG"].iter().find_map(|var| env.var(var))?;
+    parse_locale_language(&locale)
+}
@@ -0,0 +1,268 @@
+#[cfg(feature = "async")]
+mod asynchronous;
+mod cli;
+mod color;
+mod config;
+mod csv;
+mod env;
+mod error;
+mod history;
+mod input;
+mod json;
+mod lang;
+mod name;
+mod salutation;
+mod stats;
+mod template;
+mod wrap;
+mod xml;
+
+#[cfg(feature = "async")]
+pub use asynchronous::{append_history_async, greet_async, load_config_async};
+pub use cli::{Args, Command, GreetArgs, ResolvedConfig, VERSION, run, run_interactive};
+pub use color::{ColorChoice, highlight_name};
+pub use config::{Config, ConfigError, default_config_path, load_config};
+pub use env::Environment;
+pub use error::GreetingError;
+pub use history::{append_history, default_history_path};
+pub use input::{os_name_to_string, read_names_from, read_names_from_file};
+pub use json::escape_json_string;
+pub use lang::{
+    Language, detect_locale_language, greeting_template_for_lang, language, parse_locale_language,
+    supported_languages, template_for_lang,
+};
+pub use name::{
+    CaseMode, NormForm, ValidationError, dedupe_preserving_order, join_names, normalize_name,
+    title_case, transform_name, validate_name,
+};
+pub use salutation::{GREETING_WORDS, Phrase, greeting_word_for_hour, pick_greeting};
+pub use stats::{Stats, message_stats};
+pub use template::{TemplateContext, TemplateError, render_template};
+pub use wrap::wrap_text;
+pub use xml::escape_xml;
+
+use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// The output formats supported by the `--format` flag.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Plain,
+    Json,
+    /// One JSON object per line
+    Ndjson,
+    Yaml,
+    Toml,
+    /// A `message` header followed by one row per greeting
+    Csv,
+    /// A `<greeting>` element, wrapped in `<greetings>` for several names
+    Xml,
+}
+
+impl Format {
+    /// The name of the format as given to `--format`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Plain => "plain",
+            Format::Json => "json",
+            Format::Ndjson => "ndjson",
+            Format::Yaml => "yaml",
+            Format::Toml => "toml",
+            Format::Csv => "csv",
+            Format::Xml => "xml",
+        }
+    }
+}
+
+/// The names of every output format, in the order `--help` lists them.
+pub fn supported_formats() -> Vec<&'static str> {
+    Format::value_variants()
+        .iter()
+        .map(|format| format.name())
+        .collect()
+}
+
+/// A rendered greeting, ready to be printed in one of the output formats.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Output {
+    message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    timestamp: Option<String>,
+}
+
+impl Output {
+    fn new(message: String) -> Output {
+        Output {
+            message,
+            timestamp: None,
+        }
+    }
+
+    /// Stamps the greeting with `at`, rendered as ISO 8601 in UTC.
+    pub fn with_timestamp(mut self, at: DateTime<Utc>) -> Output {
+        self.timestamp = Some(at.format("%Y-%m-%dT%H:%M:%SZ").to_string());
+        self
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn timestamp(&self) -> Option<&str> {
+        self.timestamp.as_deref()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("serializing a greeting cannot fail")
+    }
+
+    /// Renders the greeting as JSON indented by two spaces.
+    pub fn to_json_pretty(&self) -> String {
+        serde_json::to_string_pretty(self).expect("serializing a greeting cannot fail")
+    }
+
+    /// Renders the greeting as a YAML mapping with double-quoted values.
+    pub fn to_yaml(&self) -> String {
+        let mut yaml = format!("message: \"{}\"", json::escape_quoted_string(&self.message));
+        if let Some(timestamp) = &self.timestamp {
+            yaml.push_str(&format!("\ntimestamp: \"{}\"", timestamp));
+        }
+        yaml
+    }
+
+    /// Renders the greeting as TOML key/value pairs using basic strings.
+    pub fn to_toml(&self) -> String {
+        let mut toml = format!(
+            "message = \"{}\"",
+            json::escape_quoted_string(&self.message)
+        );
+        if let Some(timestamp) = &self.timestamp {
+            toml.push_str(&format!("\ntimestamp = \"{}\"", timestamp));
+        }
+        toml
+    }
+
+    /// Renders the greeting as a single CSV row, without a line terminator.
+    pub fn to_csv_record(&self) -> String {
+        let message = csv::escape_csv_field(&self.message);
+        match &self.timestamp {
+            Some(timestamp) => format!("{},{}", message, timestamp),
+            None => message,
+        }
+    }
+
+    /// Renders the greeting as a `<greeting>` element.
+    pub fn to_xml(&self) -> String {
+        let mut xml = format!("<greeting><message>{}</message>", escape_xml(&self.message));
+        if let Some(timestamp) = &self.timestamp {
+            xml.push_str(&format!("<timestamp>{}</timestamp>", timestamp));
+        }
+        xml.push_str("</greeting>");
+        xml
+    }
+
+    pub fn to_plain_text(&self) -> String {
+        match &self.timestamp {
+            Some(timestamp) => format!("{} [{}]", self.message, timestamp),
+            None => self.message.clone(),
+        }
+    }
+}
+
+/// JSON output wrapped with the number of greetings, as written by `--envelope`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Envelope<'a> {
+    pub count: usize,
+    pub greetings: &'a [Output],
+}
+
+impl<'a> Envelope<'a> {
+    pub fn new(greetings: &'a [Output]) -> Self {
+        Envelope {
+            count: greetings.len(),
+            greetings,
+        }
+    }
+}
+
+/// Put before a greeting decorated with emoji.
+pub const EMOJI_PREFIX: &str = "👋 ";
+/// Put after a greeting decorated with emoji.
+pub const EMOJI_SUFFIX: &str = " ✨";
+
+/// Builds `{word}{separator}{name}{punctuation}` messages, used whenever no
+/// custom template is given.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MessageBuilder<'a> {
+    pub word: &'a str,
+    pub separator: &'a str,
+    pub punctuation: &'a str,
+    /// Surround the message with [`EMOJI_PREFIX`] and [`EMOJI_SUFFIX`].
+    pub emoji: bool,
+}
+
+impl Default for MessageBuilder<'_> {
+    fn default() -> Self {
+        MessageBuilder {
+            word: Phrase::Hello.word(),
+            separator: ", ",
+            punctuation: "!",
+            emoji: false,
+        }
+    }
+}
+
+impl MessageBuilder<'_> {
+    pub fn build(&self, name: &str) -> Output {
+        let mut message = String::new();
+        self.build_into(&mut message, name);
+        Output::new(message)
+    }
+
+    /// The byte offset at which [`build`](Self::build) places the name.
+    pub fn name_offset(&self) -> usize {
+        let prefix = if self.emoji { EMOJI_PREFIX.len() } else { 0 };
+        prefix + self.word.len() + self.separator.len()
+    }
+
+    /// Appends the message for `name` to `buf`, so that callers greeting many
+    /// names can reuse one allocation.
+    pub fn build_into(&self, buf: &mut String, name: &str) {
+        buf.reserve(self.word.len() + self.separator.len() + name.len() + self.punctuation.len());
+        if self.emoji {
+            buf.push_str(EMOJI_PREFIX);
+        }
+        buf.push_str(self.word);
+        buf.push_str(self.separator);
+        buf.push_str(name);
+        buf.push_str(self.punctuation);
+        if self.emoji {
+            buf.push_str(EMOJI_SUFFIX);
+        }
+    }
+}
+
+/// Greets `name` with the default `Hello, {name}!` message.
+pub fn greet(name: &str) -> Output {
+    MessageBuilder::default().build(name)
+}
+
+/// Greets `name` with `word` in place of the default "Hello".
+pub fn greet_with_word(word: &str, name: &str) -> Output {
+    MessageBuilder {
+        word,
+        ..MessageBuilder::default()
+    }
+    .build(name)
+}
+
+/// Greets `name` using a custom template; see [`render_template`].
+pub fn greet_with_template(template: &str, name: &str) -> Result<Output, GreetingError> {
+    Ok(Output::new(render_template(
+        template,
+        &TemplateContext::new(name),
+    )?))
+}
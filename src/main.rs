@@ -1,42 +1,169 @@
+use std::ffi::OsString;
+use std::fs::{self, File, Permissions};
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::Path;
+use std::process::ExitCode;
+
 use clap::Parser;
+use greeting::{Args, ConfigError, GreetingError, run, run_interactive};
+use log::LevelFilter;
 
-#[derive(Parser)]
-#[command(name = "greeting")]
-#[command(about = "A simple greeting application")]
-struct Args {
-    /// Name of the user to greet
-    #[arg(short, long, default_value = "World")]
-    user: String,
+/// Exit status for invalid arguments or input that fails validation.
+const EXIT_USAGE: u8 = 2;
+/// Exit status for runtime failures such as I/O errors.
+const EXIT_FAILURE: u8 = 1;
 
-    #[arg(long)]
-    json: bool,
-}
+fn main() -> ExitCode {
+    let args = match Args::try_parse() {
+        Ok(args) => args,
+        Err(err) => {
+            // `--help` and `--version` are reported as errors by clap but go to stdout.
+            let _ = err.print();
+            return if err.use_stderr() {
+                ExitCode::from(EXIT_USAGE)
+            } else {
+                ExitCode::SUCCESS
+            };
+        }
+    };
 
-struct Output {
-    message: String,
+    let to_terminal = args.greet_args().output.is_none() && io::stdout().is_terminal();
+    let args = args.with_terminal(to_terminal);
+    let greet = args.greet_args();
+    init_logging(greet.verbose);
+
+    let result = match &greet.output {
+        Some(path) => run_to_file(&args, path),
+        None if greet.interactive => {
+            run_interactive(&args, io::stdin().lock(), io::stdout().lock())
+        }
+        None => run(&args, &mut io::stdout().lock()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::from(exit_status(&err))
+        }
+    }
 }
 
-impl Output {
-    fn to_json(&self) -> String {
-        format!("{{\"message\": \"{}\"}}", self.message)
+/// Bad input from the user is a usage error; anything the environment failed at is not.
+fn exit_status(err: &GreetingError) -> u8 {
+    match err {
+        GreetingError::InvalidName(_)
+        | GreetingError::BadTemplate(_)
+        | GreetingError::UnsupportedLanguage(_)
+        | GreetingError::TooManyGreetings { .. }
+        | GreetingError::NeedsJson { .. }
+        | GreetingError::Unrepresentable { .. }
+        | GreetingError::Config(ConfigError::Parse { .. }) => EXIT_USAGE,
+        GreetingError::Io(_) | GreetingError::Config(ConfigError::Read { .. }) => EXIT_FAILURE,
     }
+}
+
+/// Logs to stderr at a level chosen by the number of `-v` flags; silent by default.
+fn init_logging(verbosity: u8) {
+    let level = match verbosity {
+        0 => LevelFilter::Off,
+        1 => LevelFilter::Info,
+        2 => LevelFilter::Debug,
+        _ => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .target(env_logger::Target::Stderr)
+        .init();
+}
 
-    fn to_plain_text(&self) -> String {
-        self.message.clone()
+/// Writes greetings to `path`, creating or truncating it, but leaves it
+/// untouched if the run fails.
+///
+/// An existing regular file, including one behind a symlink, is replaced by a
+/// temporary file given its permissions. Other targets, such as `/dev/null`,
+/// are written through.
+fn run_to_file(args: &Args, path: &Path) -> Result<(), GreetingError> {
+    match fs::metadata(path) {
+        Ok(metadata) if metadata.is_file() => {
+            let target = fs::canonicalize(path).map_err(|err| with_path(path, err))?;
+            replace_file(args, path, &target, metadata.permissions())
+        }
+        Ok(_) => write_greetings(args, create(path)?, path),
+        Err(err) if err.kind() != io::ErrorKind::NotFound => Err(with_path(path, err).into()),
+        // A dangling symlink is followed, like `File::create` does, so there
+        // is no file of ours to clean up.
+        Err(_) if fs::symlink_metadata(path).is_ok() => write_greetings(args, create(path)?, path),
+        Err(_) => {
+            let result = write_greetings(args, create(path)?, path);
+            if result.is_err() {
+                let _ = fs::remove_file(path);
+            }
+            result
+        }
     }
 }
 
-fn main() {
-    let args = Args::parse();
-    let json_output = args.json;
+/// Writes greetings to a hidden temporary file next to `target` and renames
+/// it over `target` on success.
+fn replace_file(
+    args: &Args,
+    path: &Path,
+    target: &Path,
+    permissions: Permissions,
+) -> Result<(), GreetingError> {
+    let mut prefix = OsString::from(".");
+    prefix.push(target.file_name().unwrap_or_default());
+    prefix.push(".");
+    let temp = tempfile::Builder::new()
+        .prefix(&prefix)
+        .suffix(".tmp")
+        .tempfile_in(target.parent().unwrap_or(Path::new(".")))
+        .map_err(|err| with_path(path, err))?;
+    temp.as_file()
+        .set_permissions(permissions)
+        .map_err(|err| with_path(path, err))?;
+    write_greetings(args, temp.as_file(), path)?;
+    temp.persist(target)
+        .map_err(|err| with_path(path, err.error))?;
+    Ok(())
+}
 
-    let output = Output {
-        message: format!("Hello, {}!", args.user),
-    };
+fn create(path: &Path) -> Result<File, GreetingError> {
+    Ok(File::create(path).map_err(|err| with_path(path, err))?)
+}
 
-    if json_output {
-        println!("{}", output.to_json());
+/// Runs `args` with greetings written to `file`, which errors name as `path`.
+fn write_greetings(args: &Args, file: impl Write, path: &Path) -> Result<(), GreetingError> {
+    let mut out = WithPath {
+        inner: BufWriter::new(file),
+        path,
+    };
+    if args.greet_args().interactive {
+        run_interactive(args, io::stdin().lock(), &mut out)?;
     } else {
-        println!("{}", output.to_plain_text());
+        run(args, &mut out)?;
+    }
+    Ok(out.flush()?)
+}
+
+fn with_path(path: &Path, err: io::Error) -> io::Error {
+    io::Error::new(err.kind(), format!("{}: {}", path.display(), err))
+}
+
+/// Names `path` in the errors of writes to `inner`.
+struct WithPath<'a, W> {
+    inner: W,
+    path: &'a Path,
+}
+
+impl<W: Write> Write for WithPath<'_, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner
+            .write(buf)
+            .map_err(|err| with_path(self.path, err))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush().map_err(|err| with_path(self.path, err))
     }
 }
@@ -0,0 +1,115 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use clap::ValueEnum;
+use unicode_normalization::UnicodeNormalization;
+
+/// How to change the case of a name before it is greeted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    #[default]
+    None,
+    Upper,
+    Lower,
+    Title,
+}
+
+/// Applies `case` to `name` using Unicode case mapping, so `é` becomes `É`.
+pub fn transform_name(name: &str, case: CaseMode) -> String {
+    match case {
+        CaseMode::None => name.to_string(),
+        CaseMode::Upper => name.to_uppercase(),
+        CaseMode::Lower => name.to_lowercase(),
+        CaseMode::Title => title_case(name),
+    }
+}
+
+/// Capitalizes the first letter of each whitespace-separated word.
+///
+/// The rest of each word is left untouched, so `mary jane` becomes `Mary Jane`
+/// while acronyms and names like `NASA` or `McDonald` keep their casing.
+pub fn title_case(name: &str) -> String {
+    let mut titled = String::with_capacity(name.len());
+    let mut at_word_start = true;
+    for c in name.chars() {
+        if at_word_start {
+            titled.extend(c.to_uppercase());
+        } else {
+            titled.push(c);
+        }
+        at_word_start = c.is_whitespace();
+    }
+    titled
+}
+
+/// Unicode normalization forms accepted by `--normalize`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum NormForm {
+    /// Canonical composition, e.g. `e` + U+0301 becomes `é`
+    Nfc,
+    /// Canonical decomposition, e.g. `é` becomes `e` + U+0301
+    Nfd,
+}
+
+/// Converts `name` to the Unicode normalization `form`.
+pub fn normalize_name(name: &str, form: NormForm) -> String {
+    match form {
+        NormForm::Nfc => name.nfc().collect(),
+        NormForm::Nfd => name.nfd().collect(),
+    }
+}
+
+/// Joins names as an English list with an Oxford comma: `A`, `A and B`, `A, B, and C`.
+pub fn join_names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first, second] => format!("{} and {}", first, second),
+        [rest @ .., last] => format!("{}, and {}", rest.join(", "), last),
+    }
+}
+
+/// Removes repeated names, keeping the first occurrence of each.
+pub fn dedupe_preserving_order(mut names: Vec<String>) -> Vec<String> {
+    // Borrow the names to find the repeats, then drop those in place, so that
+    // no name is copied.
+    let mut seen = HashSet::with_capacity(names.len());
+    let keep: Vec<bool> = names
+        .iter()
+        .map(|name| seen.insert(name.as_str()))
+        .collect();
+    drop(seen);
+    let mut keep = keep.into_iter();
+    names.retain(|_| keep.next().unwrap_or(true));
+    names
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ValidationError {
+    ControlCharacter { name: String, character: char },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::ControlCharacter { name, character } => write!(
+                f,
+                "name {:?} contains control character U+{:04X}",
+                name, *character as u32
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
+/// Rejects names containing ASCII control characters such as `\n` or `\t`.
+pub fn validate_name(name: &str) -> Result<(), ValidationError> {
+    match name.chars().find(char::is_ascii_control) {
+        Some(character) => Err(ValidationError::ControlCharacter {
+            name: name.to_string(),
+            character,
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,38 @@
+use rand::Rng;
+use rand::seq::IndexedRandom;
+
+/// The kind of message to build for each name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Phrase {
+    #[default]
+    Hello,
+    Goodbye,
+}
+
+impl Phrase {
+    /// The English word used by the default message builder.
+    pub fn word(self) -> &'static str {
+        match self {
+            Phrase::Hello => "Hello",
+            Phrase::Goodbye => "Goodbye",
+        }
+    }
+}
+
+/// Picks the greeting word appropriate for `hour` (0–23, local time).
+pub fn greeting_word_for_hour(hour: u32) -> &'static str {
+    match hour {
+        5..=11 => "Good morning",
+        12..=16 => "Good afternoon",
+        17..=20 => "Good evening",
+        _ => "Good night",
+    }
+}
+
+/// The words `--random` picks from.
+pub const GREETING_WORDS: &[&str] = &["Hello", "Hi", "Hey", "Greetings", "Howdy"];
+
+/// Picks a greeting word from [`GREETING_WORDS`] using `rng`.
+pub fn pick_greeting(rng: &mut impl Rng) -> &'static str {
+    GREETING_WORDS.choose(rng).copied().unwrap_or("Hello")
+}
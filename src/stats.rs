@@ -0,0 +1,19 @@
+/// Sizes of a message, which differ for non-ASCII text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Stats {
+    /// Unicode scalar values.
+    pub chars: usize,
+    /// UTF-8 bytes.
+    pub bytes: usize,
+    /// Whitespace-separated words.
+    pub words: usize,
+}
+
+/// Counts the characters, bytes and words of `msg`.
+pub fn message_stats(msg: &str) -> Stats {
+    Stats {
+        chars: msg.chars().count(),
+        bytes: msg.len(),
+        words: msg.split_whitespace().count(),
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub enum TemplateError {
+    UnknownPlaceholder(String),
+    UnclosedPlaceholder,
+    UnmatchedClosingBrace,
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TemplateError::UnknownPlaceholder(placeholder) => write!(
+                f,
+                "unknown placeholder `{{{}}}` in template (expected one of {})",
+                placeholder,
+                PLACEHOLDERS
+                    .iter()
+                    .map(|name| format!("`{{{}}}`", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            TemplateError::UnclosedPlaceholder => {
+                write!(
+                    f,
+                    "unclosed `{{` in template (use `{{{{` for a literal brace)"
+                )
+            }
+            TemplateError::UnmatchedClosingBrace => {
+                write!(
+                    f,
+                    "unmatched `}}` in template (use `}}}}` for a literal brace)"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for TemplateError {}
+
+/// The placeholders understood by [`render_template`].
+const PLACEHOLDERS: [&str; 6] = ["name", "upper", "lower", "len", "index", "total"];
+
+/// What a template is rendered with: the name being greeted and its 1-based
+/// position among the `total` greetings of a run.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TemplateContext<'a> {
+    pub name: &'a str,
+    pub index: usize,
+    pub total: usize,
+}
+
+impl<'a> TemplateContext<'a> {
+    /// The context for greeting `name` on its own.
+    pub fn new(name: &'a str) -> Self {
+        TemplateContext {
+            name,
+            index: 1,
+            total: 1,
+        }
+    }
+}
+
+/// Renders `template` for `context`.
+///
+/// `{name}` is replaced with the name, `{upper}` and `{lower}` with it
+/// upper- or lowercased, `{len}` with its length in characters, and
+/// `{index}` and `{total}` with its position and the number of greetings.
+/// `{{` and `}}` produce literal braces; any other placeholder is an error.
+pub fn render_template(template: &str, context: &TemplateContext) -> Result<String, TemplateError> {
+    render_template_locating_name(template, context).map(|(rendered, _)| rendered)
+}
+
+/// Like [`render_template`], also returning the byte offset of the first
+/// `{name}` in the result.
+pub(crate) fn render_template_locating_name(
+    template: &str,
+    context: &TemplateContext,
+) -> Result<(String, Option<usize>), TemplateError> {
+    let name = context.name;
+    let mut rendered = String::with_capacity(template.len() + name.len());
+    let mut name_start = None;
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                rendered.push('{');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(TemplateError::UnclosedPlaceholder),
+                    }
+                }
+                match placeholder.as_str() {
+                    "name" => {
+                        name_start.get_or_insert(rendered.len());
+                        rendered.push_str(name);
+                    }
+                    "upper" => rendered.push_str(&name.to_uppercase()),
+                    "lower" => rendered.push_str(&name.to_lowercase()),
+                    "len" => rendered.push_str(&name.chars().count().to_string()),
+                    "index" => rendered.push_str(&context.index.to_string()),
+                    "total" => rendered.push_str(&context.total.to_string()),
+                    _ => return Err(TemplateError::UnknownPlaceholder(placeholder)),
+                }
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                rendered.push('}');
+            }
+            '}' => return Err(TemplateError::UnmatchedClosingBrace),
+            c => rendered.push(c),
+        }
+    }
+    Ok((rendered, name_start))
+}
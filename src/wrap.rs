@@ -0,0 +1,26 @@
+/// Wraps `s` at `width` characters, breaking only at whitespace.
+///
+/// Existing line breaks are kept, whitespace between words collapses to one space,
+/// and words longer than `width` are left on a line of their own.
+pub fn wrap_text(s: &str, width: usize) -> String {
+    let mut wrapped = Vec::new();
+    for line in s.split('\n') {
+        let mut current = String::new();
+        let mut current_width = 0;
+        for word in line.split_whitespace() {
+            let word_width = word.chars().count();
+            if current_width > 0 && current_width + 1 + word_width > width {
+                wrapped.push(std::mem::take(&mut current));
+                current_width = 0;
+            }
+            if current_width > 0 {
+                current.push(' ');
+                current_width += 1;
+            }
+            current.push_str(word);
+            current_width += word_width;
+        }
+        wrapped.push(current);
+    }
+    wrapped.join("\n")
+}
@@ -0,0 +1,24 @@
+/// Escapes the XML special characters `<`, `>`, `&`, `"` and `'` in `s`.
+pub fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The first character of `s` that XML 1.0 can't represent even as a
+/// character reference: C0 controls other than tab, newline and carriage
+/// return, and the noncharacters U+FFFE and U+FFFF.
+pub(crate) fn first_unrepresentable_char(s: &str) -> Option<char> {
+    s.chars().find(|&c| {
+        matches!(c, '\u{0}'..='\u{8}' | '\u{b}' | '\u{c}' | '\u{e}'..='\u{1f}' | '\u{fffe}' | '\u{ffff}')
+    })
+}
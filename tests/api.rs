@@ -0,0 +1,380 @@
+use std::io::Cursor;
+
+use chrono::{TimeZone, Utc};
+use clap::ValueEnum;
+use greeting::{
+    CaseMode, ColorChoice, Format, GreetingError, MessageBuilder, NormForm, Output, Stats,
+    TemplateContext, TemplateError, ValidationError, dedupe_preserving_order, escape_json_string,
+    escape_xml, greet, greet_with_template, greeting_template_for_lang, greeting_word_for_hour,
+    highlight_name, join_names, message_stats, normalize_name, os_name_to_string,
+    parse_locale_language, pick_greeting, read_names_from, read_names_from_file, render_template,
+    supported_formats, supported_languages, title_case, transform_name, validate_name, wrap_text,
+};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+#[test]
+fn greet_default_message() {
+    let output = greet("Alice");
+    assert_eq!(output.to_plain_text(), "Hello, Alice!");
+    assert_eq!(output.to_json(), r#"{"message":"Hello, Alice!"}"#);
+}
+
+#[test]
+fn json_escapes_special_characters() {
+    let output = greet("O\"Brien\\\n😀");
+    assert_eq!(output.to_json(), r#"{"message":"Hello, O\"Brien\\\n😀!"}"#);
+}
+
+#[test]
+fn custom_template() {
+    let output = greet_with_template("{{{name}}} says hi", "Bob").unwrap();
+    assert_eq!(output.to_plain_text(), "{Bob} says hi");
+
+    assert!(matches!(
+        greet_with_template("Hi, {nom}", "Bob"),
+        Err(GreetingError::BadTemplate(TemplateError::UnknownPlaceholder(p))) if p == "nom"
+    ));
+}
+
+#[test]
+fn formats_round_trip_through_parsers() {
+    let name = "O\"Brien\\\t😀";
+    let expected = format!("Hello, {}!", name);
+    let output = greet(name);
+
+    let json: serde_json::Value = serde_json::from_str(&output.to_json()).unwrap();
+    assert_eq!(json["message"], expected.as_str());
+
+    let yaml: serde_yaml::Value = serde_yaml::from_str(&output.to_yaml()).unwrap();
+    assert_eq!(yaml["message"].as_str(), Some(expected.as_str()));
+
+    let toml: toml::Table = output.to_toml().parse().unwrap();
+    assert_eq!(toml["message"].as_str(), Some(expected.as_str()));
+}
+
+#[test]
+fn del_and_c1_controls_round_trip_through_yaml_and_toml() {
+    for name in [
+        "A\u{7f}B",
+        "A\u{81}B",
+        "A\u{85}B",
+        "A\u{9f}B",
+        "A\u{fffe}B",
+        "\u{1}\u{1f}",
+    ] {
+        let expected = format!("Hello, {}!", name);
+        let output = greet(name);
+
+        let yaml: serde_yaml::Value = serde_yaml::from_str(&output.to_yaml())
+            .unwrap_or_else(|err| panic!("{:?}: {}", output.to_yaml(), err));
+        assert_eq!(yaml["message"].as_str(), Some(expected.as_str()));
+
+        let toml: toml::Table = output
+            .to_toml()
+            .parse()
+            .unwrap_or_else(|err| panic!("{:?}: {}", output.to_toml(), err));
+        assert_eq!(toml["message"].as_str(), Some(expected.as_str()));
+    }
+}
+
+#[test]
+fn template_placeholders() {
+    let context = TemplateContext {
+        name: "Zoë",
+        index: 3,
+        total: 4,
+    };
+    let render = |template| render_template(template, &context).unwrap();
+    assert_eq!(render("{name}"), "Zoë");
+    assert_eq!(render("{upper}"), "ZOË");
+    assert_eq!(render("{lower}"), "zoë");
+    assert_eq!(render("{len}"), "3");
+    assert_eq!(render("{index}"), "3");
+    assert_eq!(render("{total}"), "4");
+    assert_eq!(
+        render("{index}/{total}: HELLO {upper} ({len} letters, {{{lower}}})"),
+        "3/4: HELLO ZOË (3 letters, {zoë})"
+    );
+    assert!(matches!(
+        render_template("{Upper}", &context),
+        Err(TemplateError::UnknownPlaceholder(p)) if p == "Upper"
+    ));
+}
+
+#[test]
+fn dedupe_keeps_first_occurrences_in_order() {
+    let names = ["Carol", "Alice", "Carol", "Bob", "Alice"].map(String::from);
+    assert_eq!(
+        dedupe_preserving_order(names.to_vec()),
+        ["Carol", "Alice", "Bob"]
+    );
+    assert!(dedupe_preserving_order(Vec::new()).is_empty());
+}
+
+#[test]
+fn read_names_skips_blank_lines() {
+    let input = Cursor::new("Alice  \n\n  Bob\t\n   \nCarol");
+    let names = read_names_from(input).unwrap();
+    assert_eq!(names, ["Alice", "  Bob", "Carol"]);
+}
+
+#[test]
+fn read_names_from_file_skips_blanks_and_comments() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("names.txt");
+    std::fs::write(
+        &path,
+        "# guests\nAlice\n\n   \n  Bob\t\n  # not Carol\nDave\n",
+    )
+    .unwrap();
+    let names = read_names_from_file(&path).unwrap();
+    assert_eq!(names, ["Alice", "Bob", "Dave"]);
+
+    let err = read_names_from_file(&dir.path().join("missing.txt")).unwrap_err();
+    assert!(matches!(err, GreetingError::Io(_)));
+    assert!(err.to_string().contains("missing.txt"), "{}", err);
+}
+
+#[test]
+fn greeting_word_boundaries() {
+    let expected = [
+        (0, "Good night"),
+        (4, "Good night"),
+        (5, "Good morning"),
+        (11, "Good morning"),
+        (12, "Good afternoon"),
+        (16, "Good afternoon"),
+        (17, "Good evening"),
+        (20, "Good evening"),
+        (21, "Good night"),
+        (23, "Good night"),
+    ];
+    for (hour, word) in expected {
+        assert_eq!(greeting_word_for_hour(hour), word, "hour {}", hour);
+    }
+}
+
+#[test]
+fn color_choice_respects_terminal_and_no_color() {
+    assert!(ColorChoice::Auto.should_colorize(true, false));
+    assert!(!ColorChoice::Auto.should_colorize(false, false));
+    assert!(!ColorChoice::Auto.should_colorize(true, true));
+    assert!(ColorChoice::Always.should_colorize(false, true));
+    assert!(!ColorChoice::Never.should_colorize(true, false));
+}
+
+#[test]
+fn highlight_wraps_only_the_name() {
+    assert_eq!(
+        highlight_name("Hello, Alice!", "Alice", 7),
+        "Hello, \x1b[1;36mAlice\x1b[0m!"
+    );
+    assert_eq!(
+        highlight_name("Hello, o!", "o", 7),
+        "Hello, \x1b[1;36mo\x1b[0m!"
+    );
+    assert_eq!(highlight_name("Hello, Alice!", "Bob", 7), "Hello, Alice!");
+    assert_eq!(
+        highlight_name("Hello, Alice!", "Alice", 99),
+        "Hello, Alice!"
+    );
+}
+
+#[test]
+fn pretty_json_for_single_greeting() {
+    assert_eq!(
+        greet("Alice").to_json_pretty(),
+        "{\n  \"message\": \"Hello, Alice!\"\n}"
+    );
+}
+
+#[test]
+fn transform_name_is_unicode_aware() {
+    assert_eq!(transform_name("élodie", CaseMode::Upper), "ÉLODIE");
+    assert_eq!(transform_name("ÉLODIE", CaseMode::Lower), "élodie");
+    assert_eq!(transform_name("Élodie", CaseMode::None), "Élodie");
+}
+
+#[test]
+fn output_round_trips_through_serde() {
+    let output = greet("Zoë 🎉");
+    let parsed: Output = serde_json::from_str(&output.to_json()).unwrap();
+    assert_eq!(parsed, output);
+    assert_eq!(parsed.message(), "Hello, Zoë 🎉!");
+}
+
+#[test]
+fn validate_name_rejects_control_characters() {
+    assert_eq!(validate_name("Mary Jane"), Ok(()));
+    assert_eq!(
+        validate_name("Alice\nBob"),
+        Err(ValidationError::ControlCharacter {
+            name: "Alice\nBob".to_string(),
+            character: '\n',
+        })
+    );
+}
+
+#[test]
+fn normalize_name_composes_and_decomposes() {
+    let decomposed = "Rene\u{301}e";
+    let composed = "Ren\u{e9}e";
+    assert_eq!(normalize_name(decomposed, NormForm::Nfc), composed);
+    assert_eq!(normalize_name(composed, NormForm::Nfd), decomposed);
+}
+
+#[test]
+fn csv_record_quotes_commas_and_quotes() {
+    assert_eq!(greet("Alice").to_csv_record(), "\"Hello, Alice!\"");
+    assert_eq!(
+        greet("Bob \"the Builder\", Jr").to_csv_record(),
+        "\"Hello, Bob \"\"the Builder\"\", Jr!\""
+    );
+}
+
+#[test]
+fn timestamp_is_included_only_when_set() {
+    let at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    let output = greet("Alice").with_timestamp(at);
+    assert_eq!(
+        output.to_plain_text(),
+        "Hello, Alice! [2024-01-02T03:04:05Z]"
+    );
+    assert_eq!(
+        output.to_json(),
+        r#"{"message":"Hello, Alice!","timestamp":"2024-01-02T03:04:05Z"}"#
+    );
+    assert!(!greet("Alice").to_json().contains("timestamp"));
+}
+
+#[test]
+fn message_builder_uses_its_parts() {
+    let builder = MessageBuilder {
+        word: "Hey",
+        separator: " ",
+        punctuation: ".",
+        emoji: false,
+    };
+    assert_eq!(builder.build("Alice").to_plain_text(), "Hey Alice.");
+    assert_eq!(greeting_template_for_lang("es"), Some("¡Hola, {name}!"));
+}
+
+#[test]
+fn join_names_uses_oxford_comma() {
+    let names = |list: &[&str]| list.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+    assert_eq!(join_names(&names(&["Alice"])), "Alice");
+    assert_eq!(join_names(&names(&["Alice", "Bob"])), "Alice and Bob");
+    assert_eq!(
+        join_names(&names(&["Alice", "Bob", "Carol"])),
+        "Alice, Bob, and Carol"
+    );
+    assert_eq!(
+        join_names(&names(&["Alice", "Bob", "Carol", "Dan"])),
+        "Alice, Bob, Carol, and Dan"
+    );
+}
+
+#[test]
+fn pick_greeting_is_reproducible_with_a_seed() {
+    let mut rng = ChaCha8Rng::seed_from_u64(42);
+    let words: Vec<&str> = (0..5).map(|_| pick_greeting(&mut rng)).collect();
+    assert_eq!(words, ["Hi", "Greetings", "Hello", "Howdy", "Greetings"]);
+}
+
+#[test]
+fn title_case_capitalizes_each_word() {
+    assert_eq!(title_case("mary jane"), "Mary Jane");
+    assert_eq!(title_case("élodie  de la cruz"), "Élodie  De La Cruz");
+    assert_eq!(title_case("NASA mcDonald"), "NASA McDonald");
+    assert_eq!(
+        transform_name("ada lovelace", CaseMode::Title),
+        "Ada Lovelace"
+    );
+}
+
+#[test]
+fn xml_escapes_special_characters() {
+    assert_eq!(
+        escape_xml(r#"<a & 'b' "c">"#),
+        "&lt;a &amp; &apos;b&apos; &quot;c&quot;&gt;"
+    );
+    assert_eq!(
+        greet("<Tom & Jerry>").to_xml(),
+        "<greeting><message>Hello, &lt;Tom &amp; Jerry&gt;!</message></greeting>"
+    );
+}
+
+#[test]
+fn json_escapes_quotes_backslashes_and_control_characters() {
+    assert_eq!(escape_json_string(r#"say "hi""#), r#"say \"hi\""#);
+    assert_eq!(escape_json_string(r"C:\temp"), r"C:\\temp");
+    assert_eq!(escape_json_string("a\nb\tc"), r"a\nb\tc");
+    assert_eq!(escape_json_string("bell\u{07}"), r"bell\u0007");
+    assert_eq!(escape_json_string("crab \u{1f980}"), "crab \u{1f980}");
+    let tricky = "\"\\\n\t\u{01}\u{1f980}";
+    let parsed: String =
+        serde_json::from_str(&format!("\"{}\"", escape_json_string(tricky))).unwrap();
+    assert_eq!(parsed, tricky);
+}
+
+#[test]
+fn wrap_text_breaks_on_word_boundaries() {
+    assert_eq!(wrap_text("Hello, Alice!", 20), "Hello, Alice!");
+    assert_eq!(wrap_text("Hello, Alice!", 13), "Hello, Alice!");
+    assert_eq!(
+        wrap_text("Hello, Alice, Bob, Carol, and Dan!", 12),
+        "Hello,\nAlice, Bob,\nCarol, and\nDan!"
+    );
+    assert_eq!(wrap_text("Hi Bartholomew", 5), "Hi\nBartholomew");
+}
+
+#[test]
+fn parse_locale_language_maps_to_supported_codes() {
+    assert_eq!(parse_locale_language("fr_FR.UTF-8").as_deref(), Some("fr"));
+    assert_eq!(parse_locale_language("en_US").as_deref(), Some("en"));
+    assert_eq!(parse_locale_language("de").as_deref(), Some("de"));
+    assert_eq!(parse_locale_language("C"), None);
+    assert_eq!(parse_locale_language("pt_BR.UTF-8"), None);
+}
+
+#[cfg(unix)]
+#[test]
+fn os_name_to_string_flags_lossy_conversion() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    assert_eq!(
+        os_name_to_string(OsStr::new("Zoë")),
+        ("Zoë".to_string(), false)
+    );
+    assert_eq!(
+        os_name_to_string(OsStr::from_bytes(b"Al\xffce")),
+        ("Al\u{FFFD}ce".to_string(), true)
+    );
+}
+
+#[test]
+fn supported_formats_match_format_flag_values() {
+    let values: Vec<String> = Format::value_variants()
+        .iter()
+        .map(|format| format.to_possible_value().unwrap().get_name().to_string())
+        .collect();
+    assert_eq!(supported_formats(), values);
+    assert!(supported_languages().contains(&"en"));
+}
+
+#[test]
+fn message_stats_counts_chars_bytes_and_words() {
+    let stats = message_stats("👋 Hola, José! ✨");
+    assert!(stats.chars < stats.bytes, "{:?}", stats);
+    assert_eq!(
+        stats,
+        Stats {
+            chars: 15,
+            bytes: 21,
+            words: 4,
+        }
+    );
+    assert_eq!(message_stats("").words, 0);
+}
@@ -0,0 +1,27 @@
+#![cfg(feature = "async")]
+
+use greeting::{append_history_async, greet_async, load_config_async};
+
+#[tokio::test]
+async fn greet_async_matches_greet() {
+    let output = greet_async("Alice").await;
+    assert_eq!(output.message(), "Hello, Alice!");
+}
+
+#[tokio::test]
+async fn async_config_and_history_use_tokio_fs() {
+    let dir = tempfile::tempdir().unwrap();
+    let config_path = dir.path().join("config.toml");
+    std::fs::write(&config_path, "user = \"Alice\"\n").unwrap();
+    let config = load_config_async(Some(&config_path)).await.unwrap();
+    assert_eq!(config.user.as_deref(), Some("Alice"));
+
+    let history_path = dir.path().join("nested").join("history.log");
+    append_history_async(&history_path, "Hello, Alice!")
+        .await
+        .unwrap();
+    assert_eq!(
+        std::fs::read_to_string(&history_path).unwrap(),
+        "Hello, Alice!\n"
+    );
+}
@@ -0,0 +1,288 @@
+use std::process::{Command, Output, Stdio};
+
+/// The greeting binary, run without the developer's locale, config or
+/// `GREETING_USER`.
+fn bin() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_jj-conflict-demo"));
+    command.env_clear();
+    command
+}
+
+fn greeting(args: &[&str]) -> Output {
+    bin()
+        .args(args)
+        .output()
+        .expect("failed to run greeting binary")
+}
+
+#[test]
+fn bad_format_exits_with_usage_error() {
+    let output = greeting(&["--format", "bogus"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("bogus"));
+}
+
+#[test]
+fn bad_template_exits_with_usage_error() {
+    let output = greeting(&["--template", "{nope}"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("error: "));
+}
+
+#[test]
+fn combine_with_no_names_greets_nobody() {
+    let output = bin()
+        .args(["--stdin", "--combine"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run greeting binary");
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+}
+
+#[test]
+fn help_exits_successfully() {
+    let output = greeting(&["--help"]);
+    assert!(output.status.success());
+    assert!(!output.stdout.is_empty());
+}
+
+#[test]
+fn completions_mention_command_name() {
+    for shell in ["bash", "zsh", "fish", "powershell", "elvish"] {
+        let output = greeting(&["completions", shell]);
+        assert!(output.status.success(), "{}", shell);
+        assert!(
+            String::from_utf8_lossy(&output.stdout).contains("greeting"),
+            "{}",
+            shell
+        );
+    }
+}
+
+#[test]
+fn greeting_user_env_var_sets_default_name() {
+    let output = bin().env("GREETING_USER", "Dana").output().unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Hello, Dana!\n");
+}
+
+#[test]
+fn user_flag_overrides_greeting_user_env_var() {
+    let output = bin()
+        .env("GREETING_USER", "Dana")
+        .args(["--user", "Alice"])
+        .output()
+        .unwrap();
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Hello, Alice!\n");
+}
+
+#[test]
+fn output_file_matches_stdout() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("greeting.json");
+
+    let to_file = greeting(&[
+        "-u",
+        "Alice,Bob",
+        "--json",
+        "--output",
+        path.to_str().unwrap(),
+    ]);
+    assert!(to_file.status.success());
+    assert!(to_file.stdout.is_empty());
+
+    let to_stdout = greeting(&["-u", "Alice,Bob", "--json"]);
+    assert_eq!(std::fs::read(&path).unwrap(), to_stdout.stdout);
+}
+
+#[cfg(unix)]
+#[test]
+fn output_writes_through_a_symlink() {
+    let dir = tempfile::tempdir().unwrap();
+    let real = dir.path().join("real.txt");
+    let link = dir.path().join("link.txt");
+    std::fs::write(&real, "old\n").unwrap();
+    std::os::unix::fs::symlink(&real, &link).unwrap();
+
+    let output = greeting(&["-u", "Alice", "--output", link.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+    assert_eq!(std::fs::read_to_string(&real).unwrap(), "Hello, Alice!\n");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+}
+
+#[cfg(unix)]
+#[test]
+fn output_keeps_the_file_mode() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("greeting.txt");
+    std::fs::write(&path, "old\n").unwrap();
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+
+    let output = greeting(&["-u", "Alice", "--output", path.to_str().unwrap()]);
+    assert!(output.status.success());
+    let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o600);
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "Hello, Alice!\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn output_writes_through_to_devices() {
+    let output = greeting(&["-u", "Alice", "--output", "/dev/null"]);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn unwritable_output_names_the_path() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("greeting.txt");
+
+    let output = greeting(&["--output", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&output.stderr).contains(path.to_str().unwrap()));
+}
+
+#[test]
+fn version_includes_build_metadata() {
+    let output = greeting(&["--version"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        stdout.starts_with(concat!("greeting ", env!("CARGO_PKG_VERSION"), " (")),
+        "{}",
+        stdout
+    );
+    assert!(stdout.trim_end().ends_with(')'), "{}", stdout);
+}
+
+#[test]
+fn verbose_logs_to_stderr_only() {
+    let quiet = greeting(&["-u", "Alice", "--json"]);
+    assert!(quiet.stderr.is_empty());
+
+    let verbose = greeting(&["-vv", "-u", "Alice", "--json"]);
+    assert!(verbose.status.success());
+    assert!(!verbose.stderr.is_empty());
+    let json: serde_json::Value = serde_json::from_slice(&verbose.stdout).unwrap();
+    assert_eq!(json[0]["message"], "Hello, Alice!");
+}
+
+#[test]
+fn history_failure_only_warns() {
+    let dir = tempfile::tempdir().unwrap();
+    // A file where the data directory should be makes the history unwritable.
+    let blocker = dir.path().join("data");
+    std::fs::write(&blocker, "").unwrap();
+
+    let output = bin()
+        .env("XDG_DATA_HOME", &blocker)
+        .args(["-u", "Alice", "--log-history"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "Hello, Alice!\n");
+    assert!(String::from_utf8_lossy(&output.stderr).starts_with("warning: "));
+}
+
+fn greeting_with_locale(lang: Option<&str>, args: &[&str]) -> String {
+    let mut command = bin();
+    command.args(args);
+    if let Some(lang) = lang {
+        command.env("LANG", lang);
+    }
+    String::from_utf8(command.output().unwrap().stdout).unwrap()
+}
+
+#[test]
+fn default_language_comes_from_locale() {
+    assert_eq!(
+        greeting_with_locale(Some("fr_FR.UTF-8"), &["-u", "Alice"]),
+        "Bonjour, Alice !\n"
+    );
+    assert_eq!(
+        greeting_with_locale(Some("C"), &["-u", "Alice"]),
+        "Hello, Alice!\n"
+    );
+    assert_eq!(
+        greeting_with_locale(None, &["-u", "Alice"]),
+        "Hello, Alice!\n"
+    );
+}
+
+#[test]
+fn lang_flag_overrides_locale() {
+    assert_eq!(
+        greeting_with_locale(Some("fr_FR.UTF-8"), &["-u", "Alice", "--lang", "de"]),
+        "Hallo, Alice!\n"
+    );
+}
+
+#[test]
+fn missing_names_file_exits_with_failure() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.txt");
+    let output = greeting(&["--file", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("missing.txt"));
+}
+
+#[cfg(unix)]
+#[test]
+fn non_utf8_name_is_greeted_lossily_with_a_warning() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let output = bin()
+        .arg("--user")
+        .arg(OsStr::from_bytes(b"Al\xffce,Bob"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Hello, Al\u{FFFD}ce!\nHello, Bob!\n"
+    );
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.starts_with("warning: "), "{}", stderr);
+    assert!(stderr.contains("not valid UTF-8"), "{}", stderr);
+}
+
+#[test]
+fn version_subcommand_matches_version_flag() {
+    let subcommand = greeting(&["version"]);
+    let flag = greeting(&["--version"]);
+    assert!(subcommand.status.success());
+    assert_eq!(subcommand.stdout, flag.stdout);
+}
+
+#[test]
+fn stats_go_to_stderr() {
+    let output = greeting(&["--user", "José", "--stats"]);
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "Hello, José!\n");
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "\"Hello, José!\": 12 chars, 13 bytes, 2 words\n"
+    );
+}
+
+#[test]
+fn failed_run_leaves_output_file_untouched() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("greeting.txt");
+    std::fs::write(&path, "keep\n").unwrap();
+
+    let output = greeting(&["-u", "a\nb", "--output", path.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), "keep\n");
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+}
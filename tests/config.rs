@@ -0,0 +1,142 @@
+use std::error::Error;
+use std::fs;
+
+use clap::Parser;
+use greeting::{Args, Config, ConfigError, Environment, Format, load_config, run};
+
+/// Parses `argv` with an empty environment, so that only `--config` applies.
+fn parse_args(argv: &[&str]) -> Args {
+    Args::parse_from(argv.iter().copied()).with_environment(Environment::empty())
+}
+
+fn write_config(contents: &str) -> tempfile::NamedTempFile {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    fs::write(file.path(), contents).unwrap();
+    file
+}
+
+#[test]
+fn load_config_reads_user_and_format() {
+    let file = write_config("user = \"Alice\"\nformat = \"json\"\n");
+    let config = load_config(Some(file.path())).unwrap();
+    assert_eq!(
+        config,
+        Config {
+            user: Some("Alice".to_string()),
+            format: Some(Format::Json),
+        }
+    );
+}
+
+#[test]
+fn missing_explicit_config_is_an_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let err = load_config(Some(&dir.path().join("missing.toml"))).unwrap_err();
+    assert!(matches!(err, ConfigError::Read { .. }));
+}
+
+#[test]
+fn config_errors_are_not_repeated_down_the_source_chain() {
+    let file = write_config("user = [\n");
+    let args = parse_args(&["greeting", "--config", file.path().to_str().unwrap()]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    let mut messages = vec![err.to_string()];
+    let mut source = err.source();
+    while let Some(err) = source {
+        messages.push(err.to_string());
+        source = err.source();
+    }
+    for pair in messages.windows(2) {
+        assert!(!pair[0].contains(&pair[1]), "{:?}", messages);
+    }
+}
+
+#[test]
+fn cli_flags_override_config_file() {
+    let file = write_config("user = \"Alice\"\nformat = \"json\"\n");
+    let path = file.path().to_str().unwrap();
+
+    let mut out = Vec::new();
+    run(&parse_args(&["greeting", "--config", path]), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "[{\"message\":\"Hello, Alice!\"}]\n"
+    );
+
+    let mut out = Vec::new();
+    let args = parse_args(&[
+        "greeting", "--config", path, "-u", "Bob", "--format", "plain",
+    ]);
+    run(&args, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Hello, Bob!\n");
+}
+
+#[test]
+fn dry_run_shows_flags_overriding_config_file() {
+    let file = write_config("user = \"Alice\"\nformat = \"yaml\"\n");
+    let path = file.path().to_str().unwrap();
+
+    let args = parse_args(&["greeting", "--config", path, "--dry-run"]);
+    let greet = args.greet_args();
+    let resolved = greet.resolve(&greet.load_config().unwrap(), &[]).unwrap();
+    assert_eq!(resolved.format, Format::Yaml);
+
+    let mut out = Vec::new();
+    let argv = [
+        "greeting",
+        "--config",
+        path,
+        "--dry-run",
+        "--json",
+        "--user",
+        "Bob",
+        "--lang",
+        "es",
+    ];
+    run(&parse_args(&argv), &mut out).unwrap();
+    let resolved: serde_json::Value = serde_json::from_slice(&out).unwrap();
+    assert_eq!(resolved["names"], serde_json::json!(["Bob"]));
+    assert_eq!(resolved["format"], "json");
+    assert_eq!(resolved["language"], "es");
+    assert_eq!(resolved["word"], "¡Hola");
+    assert_eq!(resolved["template"], serde_json::Value::Null);
+
+    let mut out = Vec::new();
+    run(
+        &parse_args(&["greeting", "--config", path, "--dry-run"]),
+        &mut out,
+    )
+    .unwrap();
+    let text = String::from_utf8(out).unwrap();
+    assert!(text.starts_with("names: \"Alice\"\n"), "{}", text);
+    assert!(text.contains("\nformat: yaml\n"), "{}", text);
+}
+
+#[test]
+fn default_config_is_found_through_the_environment() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::create_dir(dir.path().join("greeting")).unwrap();
+    fs::write(
+        dir.path().join("greeting/config.toml"),
+        "user = \"Alice\"\n",
+    )
+    .unwrap();
+
+    let args = Args::parse_from(["greeting"])
+        .with_environment(Environment::empty().with("XDG_CONFIG_HOME", dir.path()));
+    let mut out = Vec::new();
+    run(&args, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Hello, Alice!\n");
+}
+
+#[test]
+fn greeting_user_overrides_config_file() {
+    let file = write_config("user = \"Alice\"\n");
+    let path = file.path().to_str().unwrap();
+
+    let args = parse_args(&["greeting", "--config", path])
+        .with_environment(Environment::empty().with("GREETING_USER", "Dana"));
+    let mut out = Vec::new();
+    run(&args, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Hello, Dana!\n");
+}
@@ -0,0 +1,29 @@
+use std::fs;
+
+use clap::Parser;
+use greeting::{Args, Environment, append_history, run};
+
+#[test]
+fn append_history_creates_parents_and_appends() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nested").join("history.log");
+
+    append_history(&path, "2024-01-02T03:04:05Z\tHello, Alice!").unwrap();
+    append_history(&path, "2024-01-02T03:04:06Z\tHello, Bob!").unwrap();
+
+    assert_eq!(
+        fs::read_to_string(&path).unwrap(),
+        "2024-01-02T03:04:05Z\tHello, Alice!\n2024-01-02T03:04:06Z\tHello, Bob!\n"
+    );
+}
+
+#[test]
+fn log_history_writes_under_the_environment_data_home() {
+    let dir = tempfile::tempdir().unwrap();
+    let args = Args::parse_from(["greeting", "-u", "Alice", "--log-history"])
+        .with_environment(Environment::empty().with("XDG_DATA_HOME", dir.path()));
+    run(&args, &mut Vec::new()).unwrap();
+
+    let history = fs::read_to_string(dir.path().join("greeting/history.log")).unwrap();
+    assert!(history.ends_with("\tHello, Alice!\n"), "{}", history);
+}
@@ -0,0 +1,812 @@
+use chrono::{TimeZone, Utc};
+use clap::Parser;
+use std::error::Error;
+use std::io::Cursor;
+
+use greeting::{
+    Args, ConfigError, Environment, Format, GreetingError, Output, greet, run, run_interactive,
+};
+
+/// Parses `argv` with an empty environment, so that the developer's locale
+/// and config don't leak into the results.
+fn parse_args(argv: &[&str]) -> Args {
+    Args::parse_from(std::iter::once("greeting").chain(argv.iter().copied()))
+        .with_environment(Environment::empty())
+}
+
+fn run_with(argv: &[&str]) -> String {
+    let args = parse_args(argv);
+    let mut out = Vec::new();
+    run(&args, &mut out).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn plain_output_ends_with_newline() {
+    assert_eq!(run_with(&["--user", "Alice"]), "Hello, Alice!\n");
+    assert_eq!(
+        run_with(&["--user", "Alice,Bob"]),
+        "Hello, Alice!\nHello, Bob!\n"
+    );
+}
+
+#[test]
+fn json_output_ends_with_newline() {
+    assert_eq!(
+        run_with(&["--user", "Alice", "--json"]),
+        "[{\"message\":\"Hello, Alice!\"}]\n"
+    );
+}
+
+#[test]
+fn localized_greetings() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--lang", "es"]),
+        "¡Hola, Alice!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--lang", "fr"]),
+        "Bonjour, Alice !\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--lang", "ja", "--json"]),
+        "[{\"message\":\"こんにちは、Aliceさん！\"}]\n"
+    );
+}
+
+#[test]
+fn unsupported_language_lists_supported_codes() {
+    let args = parse_args(&["--lang", "xx"]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(&err, GreetingError::UnsupportedLanguage(code) if code == "xx"));
+    assert!(err.to_string().contains("en, es, fr, de, ja"), "{}", err);
+}
+
+#[test]
+fn color_never_applies_to_json() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--color", "always"]),
+        "Hello, \x1b[1;36mAlice\x1b[0m!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--color", "always", "--json"]),
+        "[{\"message\":\"Hello, Alice!\"}]\n"
+    );
+}
+
+#[test]
+fn color_highlights_the_name_slot_not_an_earlier_match() {
+    assert_eq!(
+        run_with(&["-u", "o", "--color", "always"]),
+        "Hello, \x1b[1;36mo\x1b[0m!\n"
+    );
+    assert_eq!(
+        run_with(&[
+            "-u",
+            "e",
+            "--color",
+            "always",
+            "--template",
+            "{name} were {name}"
+        ]),
+        "\x1b[1;36me\x1b[0m were e\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "l", "--color", "always", "--wrap", "4"]),
+        "Hello,\n\x1b[1;36ml\x1b[0m!\n"
+    );
+}
+
+#[test]
+fn pretty_json_array_is_indented() {
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob", "--json", "--pretty"]),
+        concat!(
+            "[\n",
+            "  {\n",
+            "    \"message\": \"Hello, Alice!\"\n",
+            "  },\n",
+            "  {\n",
+            "    \"message\": \"Hello, Bob!\"\n",
+            "  }\n",
+            "]\n",
+        )
+    );
+}
+
+#[test]
+fn repeat_multiplies_names() {
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob", "--repeat", "2"]),
+        "Hello, Alice!\nHello, Bob!\nHello, Alice!\nHello, Bob!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--repeat", "2", "--json"]),
+        "[{\"message\":\"Hello, Alice!\"},{\"message\":\"Hello, Alice!\"}]\n"
+    );
+    assert!(Args::try_parse_from(["greeting", "--repeat", "0"]).is_err());
+}
+
+#[test]
+fn case_flags_apply_to_all_formats() {
+    assert_eq!(run_with(&["-u", "alice", "--uppercase"]), "Hello, ALICE!\n");
+    assert_eq!(
+        run_with(&["-u", "ALICE", "--lowercase", "--json"]),
+        "[{\"message\":\"Hello, alice!\"}]\n"
+    );
+    assert!(Args::try_parse_from(["greeting", "--uppercase", "--lowercase"]).is_err());
+}
+
+#[test]
+fn quiet_suppresses_output_but_still_validates() {
+    assert_eq!(run_with(&["-u", "Alice", "--quiet"]), "");
+    assert_eq!(run_with(&["-u", "Alice", "--quiet", "--json"]), "");
+
+    let args = parse_args(&["--quiet", "--template", "{bad}"]);
+    assert!(run(&args, &mut Vec::new()).is_err());
+}
+
+#[test]
+fn ndjson_emits_one_object_per_line() {
+    let output = run_with(&["-u", "Alice,Bob,Carol", "--format", "ndjson"]);
+    let messages: Vec<String> = output
+        .lines()
+        .map(|line| {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            value["message"].as_str().unwrap().to_string()
+        })
+        .collect();
+    assert_eq!(messages, ["Hello, Alice!", "Hello, Bob!", "Hello, Carol!"]);
+}
+
+#[test]
+fn farewell_uses_goodbye_phrase() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--farewell"]),
+        "Goodbye, Alice!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--farewell", "--lang", "es", "--json"]),
+        "[{\"message\":\"¡Adiós, Alice!\"}]\n"
+    );
+}
+
+#[test]
+fn control_characters_need_allow_control() {
+    let args = parse_args(&["-u", "Alice\nBob"]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, GreetingError::InvalidName(_)));
+    assert!(err.source().is_none(), "the message already says why");
+
+    assert_eq!(
+        run_with(&["-u", "Alice\nBob", "--allow-control"]),
+        "Hello, Alice\nBob!\n"
+    );
+}
+
+#[test]
+fn interactive_greets_until_empty_line() {
+    let args = parse_args(&["--interactive", "--format", "ndjson"]);
+    let mut out = Vec::new();
+    run_interactive(&args, Cursor::new("Alice\nBob\n\nCarol\n"), &mut out).unwrap();
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "{\"message\":\"Hello, Alice!\"}\n{\"message\":\"Hello, Bob!\"}\n"
+    );
+}
+
+#[test]
+fn interactive_writes_one_document_per_session() {
+    let interact = |argv: &[&str]| {
+        let mut out = Vec::new();
+        run_interactive(&parse_args(argv), Cursor::new("Alice\nBob\n"), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        interact(&["--interactive", "--format", "csv"]),
+        "message\n\"Hello, Alice!\"\n\"Hello, Bob!\"\n"
+    );
+    assert_eq!(
+        interact(&["--interactive", "--json"]),
+        "[{\"message\":\"Hello, Alice!\"},{\"message\":\"Hello, Bob!\"}]\n"
+    );
+    assert_eq!(
+        interact(&["--interactive", "--format", "xml"]),
+        concat!(
+            "<greetings>\n",
+            "  <greeting><message>Hello, Alice!</message></greeting>\n",
+            "  <greeting><message>Hello, Bob!</message></greeting>\n",
+            "</greetings>\n"
+        )
+    );
+}
+
+#[test]
+fn interactive_rejects_a_bad_name_as_it_is_read() {
+    for format in ["plain", "json", "xml"] {
+        let args = parse_args(&["--interactive", "--format", format]);
+        let mut input = Cursor::new("A\nB\u{1}\nC\n");
+        let err = run_interactive(&args, &mut input, &mut Vec::new()).unwrap_err();
+        assert!(matches!(err, GreetingError::InvalidName(_)), "{}", format);
+        assert_eq!(input.position(), 5, "{}", format);
+    }
+}
+
+#[test]
+fn interactive_dedupes_the_same_way_in_every_format() {
+    let interact = |argv: &[&str]| {
+        let mut out = Vec::new();
+        run_interactive(&parse_args(argv), Cursor::new("Al\nBo\nAl\n"), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    let template = ["--template", "{index}/{total} {name}", "--repeat", "2"];
+    assert_eq!(
+        interact(&[&["--interactive", "--format", "ndjson"], &template[..]].concat()),
+        concat!(
+            "{\"message\":\"1/2 Al\"}\n",
+            "{\"message\":\"2/2 Al\"}\n",
+            "{\"message\":\"1/2 Bo\"}\n",
+            "{\"message\":\"2/2 Bo\"}\n"
+        )
+    );
+    assert_eq!(
+        interact(&[&["--interactive", "--json"], &template[..]].concat()),
+        concat!(
+            "[{\"message\":\"1/2 Al\"},{\"message\":\"2/2 Al\"},",
+            "{\"message\":\"1/2 Bo\"},{\"message\":\"2/2 Bo\"}]\n"
+        )
+    );
+    assert_eq!(
+        interact(&["--interactive", "--allow-duplicates", "--json"]),
+        concat!(
+            "[{\"message\":\"Hello, Al!\"},{\"message\":\"Hello, Bo!\"},",
+            "{\"message\":\"Hello, Al!\"}]\n"
+        )
+    );
+}
+
+#[test]
+fn timestamp_is_written_in_every_format() {
+    let at = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+    let stamped = |format: &str| {
+        let args = parse_args(&["-u", "Alice,Bob", "--timestamp", "--format", format]).with_now(at);
+        let mut out = Vec::new();
+        run(&args, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(
+        stamped("plain"),
+        "Hello, Alice! [2024-01-02T03:04:05Z]\nHello, Bob! [2024-01-02T03:04:05Z]\n"
+    );
+    assert_eq!(
+        stamped("json"),
+        concat!(
+            "[{\"message\":\"Hello, Alice!\",\"timestamp\":\"2024-01-02T03:04:05Z\"},",
+            "{\"message\":\"Hello, Bob!\",\"timestamp\":\"2024-01-02T03:04:05Z\"}]\n"
+        )
+    );
+    assert_eq!(
+        stamped("ndjson"),
+        concat!(
+            "{\"message\":\"Hello, Alice!\",\"timestamp\":\"2024-01-02T03:04:05Z\"}\n",
+            "{\"message\":\"Hello, Bob!\",\"timestamp\":\"2024-01-02T03:04:05Z\"}\n"
+        )
+    );
+    assert_eq!(
+        stamped("yaml"),
+        concat!(
+            "- message: \"Hello, Alice!\"\n",
+            "  timestamp: \"2024-01-02T03:04:05Z\"\n",
+            "- message: \"Hello, Bob!\"\n",
+            "  timestamp: \"2024-01-02T03:04:05Z\"\n"
+        )
+    );
+    assert_eq!(
+        stamped("toml"),
+        concat!(
+            "[[greetings]]\n",
+            "message = \"Hello, Alice!\"\n",
+            "timestamp = \"2024-01-02T03:04:05Z\"\n",
+            "\n",
+            "[[greetings]]\n",
+            "message = \"Hello, Bob!\"\n",
+            "timestamp = \"2024-01-02T03:04:05Z\"\n"
+        )
+    );
+    assert_eq!(
+        stamped("csv"),
+        concat!(
+            "message,timestamp\n",
+            "\"Hello, Alice!\",2024-01-02T03:04:05Z\n",
+            "\"Hello, Bob!\",2024-01-02T03:04:05Z\n"
+        )
+    );
+    assert_eq!(
+        stamped("xml"),
+        concat!(
+            "<greetings>\n",
+            "  <greeting><message>Hello, Alice!</message><timestamp>2024-01-02T03:04:05Z</timestamp></greeting>\n",
+            "  <greeting><message>Hello, Bob!</message><timestamp>2024-01-02T03:04:05Z</timestamp></greeting>\n",
+            "</greetings>\n"
+        )
+    );
+}
+
+#[test]
+fn xml_rejects_characters_it_cannot_represent() {
+    let args = parse_args(&["-u", "A\u{1}", "--allow-control", "--format", "xml"]);
+    let mut out = Vec::new();
+    let err = run(&args, &mut out).unwrap_err();
+    assert!(matches!(
+        err,
+        GreetingError::Unrepresentable {
+            format: Format::Xml,
+            character: '\u{1}'
+        }
+    ));
+    assert_eq!(err.to_string(), "xml output can't represent U+0001");
+    assert!(out.is_empty());
+    assert_eq!(
+        run_with(&["-u", "A\tB", "--allow-control", "--format", "xml"]),
+        "<greeting><message>Hello, A\tB!</message></greeting>\n"
+    );
+}
+
+#[test]
+fn normalize_nfc_composes_names() {
+    assert_eq!(
+        run_with(&["-u", "Rene\u{301}e", "--normalize", "nfc", "--json"]),
+        "[{\"message\":\"Hello, Ren\u{e9}e!\"}]\n"
+    );
+}
+
+#[test]
+fn csv_prints_header_once() {
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob", "--format", "csv"]),
+        "message\n\"Hello, Alice!\"\n\"Hello, Bob!\"\n"
+    );
+}
+
+#[test]
+fn punctuation_replaces_default_terminator() {
+    assert_eq!(run_with(&["-u", "Alice"]), "Hello, Alice!\n");
+    assert_eq!(
+        run_with(&["-u", "Alice", "--punctuation", "."]),
+        "Hello, Alice.\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--punctuation", ""]),
+        "Hello, Alice\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--punctuation", "?", "--json"]),
+        "[{\"message\":\"Hello, Alice?\"}]\n"
+    );
+}
+
+#[test]
+fn separator_goes_between_word_and_name() {
+    assert_eq!(run_with(&["-u", "Alice"]), "Hello, Alice!\n");
+    assert_eq!(
+        run_with(&["-u", "Alice", "--separator", " "]),
+        "Hello Alice!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--separator", ""]),
+        "HelloAlice!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--separator", " - ", "--punctuation", "."]),
+        "Hello - Alice.\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--separator", " ", "--lang", "ja"]),
+        "こんにちは Aliceさん！\n"
+    );
+}
+
+#[test]
+fn punctuation_is_ignored_with_a_template() {
+    assert_eq!(
+        run_with(&[
+            "-u",
+            "Alice",
+            "--punctuation",
+            ".",
+            "--template",
+            "Hi {name}!"
+        ]),
+        "Hi Alice!\n"
+    );
+}
+
+#[test]
+fn combine_produces_a_single_greeting() {
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob,Carol", "--combine"]),
+        "Hello, Alice, Bob, and Carol!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob", "--combine", "--json"]),
+        "[{\"message\":\"Hello, Alice and Bob!\"}]\n"
+    );
+    let dry_run = run_with(&["-u", "Alice,Bob", "--combine", "--dry-run"]);
+    assert!(
+        dry_run.starts_with("names: \"Alice and Bob\"\n"),
+        "{}",
+        dry_run
+    );
+}
+
+#[test]
+fn random_with_seed_is_reproducible() {
+    let argv = ["-u", "Alice,Bob", "--random", "--seed", "42", "--json"];
+    assert_eq!(
+        run_with(&argv),
+        "[{\"message\":\"Hi, Alice!\"},{\"message\":\"Greetings, Bob!\"}]\n"
+    );
+}
+
+#[test]
+fn template_file_renders_like_inline_template() {
+    let file = tempfile::NamedTempFile::new().unwrap();
+    std::fs::write(file.path(), "Dear {name},\nwelcome!\n").unwrap();
+    let path = file.path().to_str().unwrap();
+
+    assert_eq!(
+        run_with(&["-u", "Alice", "--template-file", path]),
+        run_with(&["-u", "Alice", "--template", "Dear {name},\nwelcome!"])
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--template-file", path]),
+        "Dear Alice,\nwelcome!\n"
+    );
+    assert!(
+        Args::try_parse_from(["greeting", "--template-file", path, "--template", "x"]).is_err()
+    );
+}
+
+#[test]
+fn xml_wraps_multiple_greetings() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--format", "xml"]),
+        "<greeting><message>Hello, Alice!</message></greeting>\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice,Bob", "--format", "xml"]),
+        concat!(
+            "<greetings>\n",
+            "  <greeting><message>Hello, Alice!</message></greeting>\n",
+            "  <greeting><message>Hello, Bob!</message></greeting>\n",
+            "</greetings>\n",
+        )
+    );
+}
+
+#[test]
+fn wrap_applies_only_to_plain_text() {
+    let argv = ["-u", "Alice,Bob,Carol", "--combine", "--wrap", "16"];
+    assert_eq!(run_with(&argv), "Hello, Alice,\nBob, and Carol!\n");
+    assert_eq!(
+        run_with(&[&argv[..], &["--json"]].concat()),
+        "[{\"message\":\"Hello, Alice, Bob, and Carol!\"}]\n"
+    );
+}
+
+#[test]
+fn bad_template_is_reported_as_such() {
+    let args = parse_args(&["--template", "Hi, {name"]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, GreetingError::BadTemplate(_)));
+}
+
+#[test]
+fn missing_template_file_is_an_io_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.txt");
+    let args = parse_args(&["--template-file", path.to_str().unwrap()]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(err, GreetingError::Io(_)));
+}
+
+#[test]
+fn missing_explicit_config_is_a_config_error() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing.toml");
+    let args = parse_args(&["--config", path.to_str().unwrap()]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        err,
+        GreetingError::Config(ConfigError::Read { .. })
+    ));
+}
+
+#[test]
+fn json_map_keys_greetings_by_name() {
+    assert_eq!(
+        run_with(&["-u", "Bob,Alice", "--json", "--json-map"]),
+        "{\"Alice\":\"Hello, Alice!\",\"Bob\":\"Hello, Bob!\"}\n"
+    );
+}
+
+#[test]
+fn json_map_keeps_the_last_greeting_for_duplicate_names() {
+    let numbered = [
+        "-u",
+        "Alice,Alice",
+        "--template",
+        "{index}. Hi, {name}",
+        "--json",
+        "--json-map",
+    ];
+    assert_eq!(run_with(&numbered), "{\"Alice\":\"1. Hi, Alice\"}\n");
+    assert_eq!(
+        run_with(&[&numbered[..], &["--allow-duplicates"]].concat()),
+        "{\"Alice\":\"2. Hi, Alice\"}\n"
+    );
+    let seeded = run_with(&[
+        "-u",
+        "Alice,Alice",
+        "--allow-duplicates",
+        "--random",
+        "--seed",
+        "1",
+        "--json",
+    ]);
+    let last: Vec<serde_json::Value> = serde_json::from_str(&seeded).unwrap();
+    let mapped = run_with(&[
+        "-u",
+        "Alice,Alice",
+        "--allow-duplicates",
+        "--random",
+        "--seed",
+        "1",
+        "--json",
+        "--json-map",
+    ]);
+    let mapped: serde_json::Value = serde_json::from_str(&mapped).unwrap();
+    assert_eq!(mapped["Alice"], last[1]["message"]);
+}
+
+#[test]
+fn json_only_flags_require_json_output() {
+    let args = parse_args(&["-u", "Alice", "--json-map"]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(matches!(
+        err,
+        GreetingError::NeedsJson {
+            flag: "--json-map",
+            format: Format::Plain
+        }
+    ));
+    let args = parse_args(&["-u", "Alice", "--envelope", "--format", "yaml"]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert_eq!(err.to_string(), "--envelope needs JSON output, not yaml");
+    assert_eq!(
+        run_with(&["-u", "Alice", "--format", "json", "--json-map"]),
+        "{\"Alice\":\"Hello, Alice!\"}\n"
+    );
+}
+
+/// Counts the calls made to the underlying writer.
+#[derive(Default)]
+struct CountingWriter {
+    bytes: Vec<u8>,
+    writes: usize,
+    flushes: usize,
+}
+
+impl std::io::Write for CountingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.writes += 1;
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.flushes += 1;
+        Ok(())
+    }
+}
+
+#[test]
+fn bulk_output_matches_one_greeting_per_name() {
+    let names: Vec<String> = (0..10_000).map(|i| format!("User{}", i)).collect();
+    let joined = names.join(",");
+    let args = parse_args(&["--color", "never", "--user", &joined]);
+    let mut out = CountingWriter::default();
+    run(&args, &mut out).unwrap();
+
+    let expected: String = names
+        .iter()
+        .map(|name| format!("{}\n", greet(name).to_plain_text()))
+        .collect();
+    assert_eq!(String::from_utf8(out.bytes).unwrap(), expected);
+    assert!(out.writes < names.len() / 100, "{} writes", out.writes);
+    assert_eq!(out.flushes, 1);
+}
+
+#[test]
+fn file_names_are_greeted() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("names.txt");
+    std::fs::write(&path, "Alice\n# skip me\n\n Bob \n").unwrap();
+    assert_eq!(
+        run_with(&["--file", path.to_str().unwrap()]),
+        "Hello, Alice!\nHello, Bob!\n"
+    );
+}
+
+#[test]
+fn envelope_count_matches_greetings() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--json", "--envelope"]),
+        "{\"count\":1,\"greetings\":[{\"message\":\"Hello, Alice!\"}]}\n"
+    );
+    let cases: [(&[&str], usize); 4] = [
+        (&["-u", "Alice,Bob,Carol"], 3),
+        (&["-u", "Alice,Bob,Carol", "--combine"], 1),
+        (&["-u", "Alice,Bob", "--repeat", "3"], 6),
+        (&["-u", "Alice,Bob", "--combine", "--repeat", "2"], 2),
+    ];
+    for (argv, expected) in cases {
+        let argv = [argv, &["--json", "--envelope"]].concat();
+        let json: serde_json::Value = serde_json::from_str(&run_with(&argv)).unwrap();
+        assert_eq!(json["count"], expected, "{:?}", argv);
+        assert_eq!(json["greetings"].as_array().unwrap().len(), expected);
+    }
+}
+
+#[test]
+fn template_index_counts_every_greeting() {
+    assert_eq!(
+        run_with(&[
+            "-u",
+            "Alice,Bob",
+            "--repeat",
+            "2",
+            "--template",
+            "{index}: HELLO {upper}",
+        ]),
+        "1: HELLO ALICE\n2: HELLO BOB\n3: HELLO ALICE\n4: HELLO BOB\n"
+    );
+}
+
+#[test]
+fn greet_subcommand_matches_implicit_greet() {
+    for argv in [
+        &["--user", "Alice"][..],
+        &["--user", "Alice,Bob", "--json", "--farewell"],
+        &["--template", "{index}: {upper}", "-u", "Alice"],
+    ] {
+        let explicit = [&["greet"][..], argv].concat();
+        assert_eq!(run_with(argv), run_with(&explicit), "{:?}", argv);
+    }
+}
+
+#[test]
+fn greet_subcommand_conflicts_with_top_level_flags() {
+    assert!(Args::try_parse_from(["greeting", "--user", "Alice", "greet"]).is_err());
+}
+
+#[test]
+fn version_subcommand_matches_version_flag() {
+    let version = run_with(&["version"]);
+    assert_eq!(version, format!("greeting {}\n", greeting::VERSION));
+}
+
+#[test]
+fn duplicate_names_are_greeted_once_unless_allowed() {
+    assert_eq!(
+        run_with(&["-u", "Bob,Alice,Bob,Carol,Alice"]),
+        "Hello, Bob!\nHello, Alice!\nHello, Carol!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Bob,Alice,Bob", "--allow-duplicates"]),
+        "Hello, Bob!\nHello, Alice!\nHello, Bob!\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "alice,Alice", "--title-case"]),
+        "Hello, Alice!\n"
+    );
+}
+
+#[test]
+fn list_capabilities_reports_formats_and_languages() {
+    let json: serde_json::Value =
+        serde_json::from_str(&run_with(&["--list-capabilities"])).unwrap();
+    let formats = json["formats"].as_array().unwrap();
+    let languages = json["languages"].as_array().unwrap();
+    assert!(formats.contains(&"json".into()), "{}", json);
+    assert!(languages.contains(&"en".into()), "{}", json);
+}
+
+#[test]
+fn list_capabilities_conflicts_with_interactive() {
+    let parsed = Args::try_parse_from(["greeting", "-i", "--list-capabilities"]);
+    let err = parsed.err().expect("the flags should conflict");
+    assert_eq!(err.kind(), clap::error::ErrorKind::ArgumentConflict);
+}
+
+#[test]
+fn emoji_decorates_plain_and_json_output() {
+    assert_eq!(
+        run_with(&["-u", "Alice", "--emoji"]),
+        "👋 Hello, Alice! ✨\n"
+    );
+    assert_eq!(
+        run_with(&["-u", "Alice", "--emoji", "--template", "{upper}!"]),
+        "👋 ALICE! ✨\n"
+    );
+
+    let json = run_with(&["-u", "Alice", "--emoji", "--json"]);
+    let outputs: Vec<Output> = serde_json::from_str(&json).unwrap();
+    assert_eq!(outputs[0].message(), "👋 Hello, Alice! ✨");
+    let escaped = r#"[{"message":"\ud83d\udc4b Hello, Alice! \u2728"}]"#;
+    let outputs: Vec<Output> = serde_json::from_str(escaped).unwrap();
+    assert_eq!(outputs[0].message(), "👋 Hello, Alice! ✨");
+}
+
+#[test]
+fn environment_pins_the_locale() {
+    let args = parse_args(&["-u", "Alice"])
+        .with_environment(Environment::empty().with("LANG", "fr_FR.UTF-8"));
+    let mut out = Vec::new();
+    run(&args, &mut out).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "Bonjour, Alice !\n");
+    assert_eq!(run_with(&["-u", "Alice"]), "Hello, Alice!\n");
+}
+
+#[test]
+fn auto_color_follows_the_output_target() {
+    let run_args = |args: Args| {
+        let mut out = Vec::new();
+        run(&args, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    };
+    assert_eq!(run_with(&["-u", "Alice"]), "Hello, Alice!\n");
+    assert_eq!(
+        run_args(parse_args(&["-u", "Alice"]).with_terminal(true)),
+        "Hello, \x1b[1;36mAlice\x1b[0m!\n"
+    );
+    let no_color = Environment::empty().with("NO_COLOR", "1");
+    assert_eq!(
+        run_args(
+            parse_args(&["-u", "Alice"])
+                .with_terminal(true)
+                .with_environment(no_color)
+        ),
+        "Hello, Alice!\n"
+    );
+}
+
+#[test]
+fn huge_repeat_counts_are_rejected_or_streamed() {
+    let args = parse_args(&["-u", "a,b", "--repeat", &u64::MAX.to_string()]);
+    let err = run(&args, &mut Vec::new()).unwrap_err();
+    assert!(
+        matches!(err, GreetingError::TooManyGreetings { names: 2, .. }),
+        "{}",
+        err
+    );
+
+    /// Accepts a few bytes, then fails like a closed pipe.
+    struct ShortWriter(usize);
+    impl std::io::Write for ShortWriter {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            if self.0 < buf.len() {
+                return Err(std::io::ErrorKind::BrokenPipe.into());
+            }
+            self.0 -= buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let args = parse_args(&["-u", "a", "--repeat", &i64::MAX.to_string()]);
+    let err = run(&args, &mut ShortWriter(1 << 20)).unwrap_err();
+    assert!(matches!(err, GreetingError::Io(_)), "{}", err);
+}